@@ -1,6 +1,13 @@
+#[cfg(feature = "render")]
 pub mod events;
+#[cfg(feature = "render")]
 pub mod gl;
+pub mod name;
+#[cfg(feature = "render")]
 pub mod simulator;
+#[cfg(feature = "render")]
 pub mod text;
+#[cfg(feature = "render")]
 pub mod texture;
+pub mod tracking;
 pub mod world;
@@ -0,0 +1,19 @@
+use hecs::{Entity, World};
+
+/// human readable name of the body
+pub struct NameComponent {
+    pub name: String,
+}
+
+impl NameComponent {
+    pub fn new(name: String) -> Self {
+        NameComponent { name }
+    }
+}
+
+/// name of the entity or its id when it has no name
+pub fn name_of(world: &World, entity: Entity) -> String {
+    world
+        .get::<&NameComponent>(entity)
+        .map_or(format!("{:?}", entity), |name| name.name.clone())
+}
@@ -1,16 +1,31 @@
-use crate::gl::ScreenResolution;
-use crate::{
-    gl, text, world, EventLoop, EventSettings, Events, LoadingStage, OpenGL, Simulation,
-    SimulationStage, WindowSettings,
-};
+use crate::core::events::EventLoop;
+use crate::core::gl::ScreenResolution;
+use crate::core::{gl, text, world};
+use crate::loader::stage::LoadingStage;
+use crate::model::Simulation;
+use crate::physics::integrator::Integrator;
+use crate::simulation::SimulationStage;
 use assets_manager::AssetCache;
 use glutin_window::GlutinWindow as Window;
+use opengl_graphics::OpenGL;
+use piston::event_loop::{EventSettings, Events};
+use piston::window::WindowSettings;
+use std::error::Error;
+use std::fs::File;
 use winit::window::Fullscreen;
 
-pub fn run(simulation_file: &str, assets_path: &str, resolution: ScreenResolution) {
-    let assets_cache = AssetCache::new(assets_path).unwrap();
-    let asset_lock = assets_cache.load::<Simulation>(simulation_file).unwrap();
+pub fn run(
+    simulation_file: &str,
+    assets_path: &str,
+    resolution: ScreenResolution,
+    integrator: Option<Integrator>,
+    diagnostics_log: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let assets_cache = AssetCache::new(assets_path)?;
+    let asset_lock = assets_cache.load::<Simulation>(simulation_file)?;
     let simulation = asset_lock.read();
+    simulation.validate()?;
+    let models = simulation.models()?;
 
     let opengl = OpenGL::V4_5;
     let mut window: Window = WindowSettings::new("n-Body Simulation", resolution.resolution())
@@ -23,7 +38,9 @@ pub fn run(simulation_file: &str, assets_path: &str, resolution: ScreenResolutio
     // workraound for the borderless fullscreen bug in pistoncore-glutin_window
     // see details here: https://github.com/PistonDevelopers/glutin_window/issues/210
     if resolution.fullscreen() {
-        window.window.set_fullscreen(Some(Fullscreen::Borderless(window.window.current_monitor())));
+        window.window.set_fullscreen(Some(Fullscreen::Borderless(
+            window.window.current_monitor(),
+        )));
     }
 
     let gl = gl::create(opengl);
@@ -32,14 +49,26 @@ pub fn run(simulation_file: &str, assets_path: &str, resolution: ScreenResolutio
     let events = Events::new(EventSettings::new());
     let mut event_loop = EventLoop::new(events);
 
-    let mut loading_stage = LoadingStage::new(
-        gl.clone(),
-        glyphs.clone(),
-        world.clone(),
-        simulation.models(),
-    );
-    let mut simulation_stage = SimulationStage::new(gl, glyphs.clone(), world);
+    let mut loading_stage = LoadingStage::new(gl.clone(), glyphs.clone(), world.clone(), models);
+    let mut universe = simulation.universe();
+    if let Some(integrator) = integrator {
+        universe.set_integrator(integrator);
+    }
+    if let Some(path) = diagnostics_log {
+        let log =
+            File::create(path).map_err(|error| format!("can't create {}: {}", path, error))?;
+        universe.log_diagnostics(log);
+    }
+    let mut simulation_stage = SimulationStage::new(gl, glyphs.clone(), world.clone(), universe);
+    simulation_stage
+        .renderer_as_mut()
+        .set_prediction(simulation.prediction());
 
     event_loop.activate_stage(&mut loading_stage, &mut window);
+    if let Some(error) = loading_stage.take_error() {
+        return Err(error.into());
+    }
+    simulation.center_bodies(&mut world.borrow_mut());
     event_loop.activate_stage(&mut simulation_stage, &mut window);
+    Ok(())
 }
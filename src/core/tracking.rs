@@ -0,0 +1,14 @@
+/// marks the body followed by the camera
+pub struct TrackingComponent {}
+
+impl Default for TrackingComponent {
+    fn default() -> Self {
+        TrackingComponent::new()
+    }
+}
+
+impl TrackingComponent {
+    pub fn new() -> Self {
+        TrackingComponent {}
+    }
+}
@@ -0,0 +1,148 @@
+use crate::core::name::NameComponent;
+use crate::loader::placement;
+use crate::model::Simulation;
+use crate::physics::gravity::MassComponent;
+use crate::physics::integrator::Integrator;
+use crate::physics::motion::Motion;
+use crate::physics::universe::Universe;
+use assets_manager::AssetCache;
+use hecs::World;
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+
+/// parameters of a simulation run without a window
+pub struct HeadlessSettings {
+    /// simulated duration in seconds
+    pub duration: f64,
+    /// physics step in simulated seconds
+    pub step: f64,
+    /// csv file receiving states of bodies
+    pub output: String,
+    /// simulated seconds between snapshots. only the final state is written without it
+    pub snapshot_interval: Option<f64>,
+}
+
+/// run the simulation for the given duration without creating a window or graphics context
+pub fn run(
+    simulation_file: &str,
+    assets_path: &str,
+    integrator: Option<Integrator>,
+    settings: HeadlessSettings,
+) -> Result<(), Box<dyn Error>> {
+    let assets_cache = AssetCache::new(assets_path)?;
+    let asset_lock = assets_cache.load::<Simulation>(simulation_file)?;
+    let simulation = asset_lock.read();
+    simulation.validate()?;
+
+    let mut world = World::new();
+    for planet in simulation.planets()? {
+        let entity = world.spawn(planet.body_builder().build());
+        placement::place(&mut world, entity)?;
+    }
+    simulation.center_bodies(&mut world);
+    let mut universe = simulation.universe();
+    if let Some(integrator) = integrator {
+        universe.set_integrator(integrator);
+    }
+
+    let file = File::create(&settings.output)
+        .map_err(|error| format!("can't create {}: {}", settings.output, error))?;
+    let mut output = BufWriter::new(file);
+    simulate(&mut world, &mut universe, &settings, &mut output)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// advance the universe for the duration of the settings and write states of bodies as csv
+pub fn simulate(
+    world: &mut World,
+    universe: &mut Universe,
+    settings: &HeadlessSettings,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(output, "time,name,mass,x,y,vx,vy")?;
+    if settings.snapshot_interval.is_some() {
+        write_state(output, world, 0.0)?;
+    }
+
+    let mut time = 0.0;
+    let mut next_snapshot = settings.snapshot_interval.unwrap_or(f64::INFINITY);
+    while time < settings.duration {
+        let dt = settings.step.min(settings.duration - time);
+        universe.step(world, dt);
+        universe.observe(world);
+        time += dt;
+        for encounter in universe.take_encounters() {
+            println!("encounter: {}", encounter);
+        }
+        for event in universe.take_soi_events() {
+            println!("soi: {}", event.describe(world));
+        }
+        if time >= next_snapshot && time < settings.duration {
+            write_state(output, world, time)?;
+            next_snapshot += settings.snapshot_interval.unwrap();
+        }
+    }
+    write_state(output, world, time)
+}
+
+fn write_state(output: &mut impl Write, world: &mut World, time: f64) -> io::Result<()> {
+    for (_id, (name, mass, motion)) in
+        &mut world.query::<(&NameComponent, &MassComponent, &Motion)>()
+    {
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{}",
+            time,
+            name.name,
+            mass.mass,
+            motion.position[0],
+            motion.position[1],
+            motion.velocity[0],
+            motion.velocity[1]
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::force::ForceComponent;
+
+    #[test]
+    fn write_snapshots_and_final_state() {
+        let mut world = World::new();
+        world.spawn((
+            NameComponent::new(String::from("Probe")),
+            MassComponent::new(1.0),
+            ForceComponent::zero(),
+            Motion::new_position_velocity([0.0, 0.0], [2.0, 0.0]),
+        ));
+        let mut universe = Universe::new();
+        let settings = HeadlessSettings {
+            duration: 10.0,
+            step: 1.0,
+            output: String::new(),
+            snapshot_interval: Some(4.0),
+        };
+        let mut output = vec![];
+        simulate(&mut world, &mut universe, &settings, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "time,name,mass,x,y,vx,vy",
+                "0,Probe,1,0,0,2,0",
+                "4,Probe,1,8,0,2,0",
+                "8,Probe,1,16,0,2,0",
+                "10,Probe,1,20,0,2,0",
+            ]
+        );
+        assert_eq!(universe.elapsed(), 10.0);
+    }
+}
@@ -0,0 +1,13 @@
+//! gravitational simulation of multiple bodies
+//!
+//! physics and simulation model don't depend on graphics.
+//! rendering with piston is available with the `render` feature.
+pub mod core;
+pub mod headless;
+pub mod loader;
+pub mod model;
+pub mod physics;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "render")]
+pub mod simulation;
@@ -1,4 +1,7 @@
 pub mod model_loader;
+pub mod placement;
+#[cfg(feature = "render")]
 pub mod screen;
+#[cfg(feature = "render")]
 pub mod stage;
 pub mod state;
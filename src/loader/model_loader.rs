@@ -1,6 +1,7 @@
+use crate::core::tracking::TrackingComponent;
+use crate::loader::placement::{self, PlacementError};
 use crate::loader::state::LoadingState;
 use crate::physics::gravity::MassComponent;
-use crate::render::camera::TrackingComponent;
 use hecs::{EntityBuilder, World};
 
 pub struct ModelLoader<'a> {
@@ -13,10 +14,15 @@ impl<'a> ModelLoader<'a> {
         ModelLoader { loaded: 0, models }
     }
 
-    pub fn update(&mut self, loading_state: &mut LoadingState, world: &mut World) {
+    pub fn update(
+        &mut self,
+        loading_state: &mut LoadingState,
+        world: &mut World,
+    ) -> Result<(), PlacementError> {
         if self.loaded < self.models.len() {
             let e = self.models.get(self.loaded).unwrap();
-            world.spawn(e.to_entity_builder().build());
+            let entity = world.spawn(e.to_entity_builder().build());
+            placement::place(world, entity)?;
             let progress = self.loaded as f64 / self.models.len() as f64;
             loading_state.set_progress(progress);
             self.loaded += 1;
@@ -38,6 +44,7 @@ impl<'a> ModelLoader<'a> {
 
             loading_state.set_progress(1.0);
         }
+        Ok(())
     }
 }
 
@@ -0,0 +1,164 @@
+use crate::core::name::{name_of, NameComponent};
+use crate::physics::gravity::{MassComponent, G};
+use crate::physics::lagrange::{pair_lagrange_points, LagrangePoint};
+use crate::physics::motion::Motion;
+use crate::physics::orbit::OrbitalElements;
+use hecs::{Entity, World};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// motion relative to other bodies. resolved into absolute motion once they are spawned
+pub enum PlacementComponent {
+    /// without an orbit the position and velocity of the entity are relative to the parent
+    Parent {
+        parent: String,
+        orbit: Option<OrbitalElements>,
+    },
+    /// position and velocity of the entity are relative to the lagrange point of the pair
+    Lagrange {
+        primary: String,
+        secondary: String,
+        point: LagrangePoint,
+    },
+}
+
+impl PlacementComponent {
+    pub fn new(parent: String, orbit: Option<OrbitalElements>) -> Self {
+        PlacementComponent::Parent { parent, orbit }
+    }
+
+    pub fn lagrange(primary: String, secondary: String, point: LagrangePoint) -> Self {
+        PlacementComponent::Lagrange {
+            primary,
+            secondary,
+            point,
+        }
+    }
+}
+
+/// body that can't be placed relative to the bodies of the scenario
+#[derive(Debug, PartialEq)]
+pub enum PlacementError {
+    UnknownBody {
+        planet: String,
+        body: String,
+    },
+    /// the pair coincides or has no mass
+    NoLagrangePoints {
+        planet: String,
+    },
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::UnknownBody { planet, body } => {
+                write!(f, "unknown body {} placing {}", body, planet)
+            }
+            PlacementError::NoLagrangePoints { planet } => write!(
+                f,
+                "no lagrange points to place {} at. the pair coincides or has no mass",
+                planet
+            ),
+        }
+    }
+}
+
+impl Error for PlacementError {}
+
+/// replace placement of the entity with absolute position and velocity
+///
+/// bodies the entity is placed relative to have to be spawned before it.
+pub fn place(world: &mut World, entity: Entity) -> Result<(), PlacementError> {
+    let placement = match world.remove_one::<PlacementComponent>(entity) {
+        Ok(placement) => placement,
+        Err(_) => return Ok(()),
+    };
+    let (origin, state) = match placement {
+        PlacementComponent::Parent { parent, orbit } => {
+            let parent = find(world, entity, &parent)?;
+            let parent_motion = *world.get::<&Motion>(parent).unwrap();
+            let parent_mass = world.get::<&MassComponent>(parent).unwrap().mass;
+            let mass = world.get::<&MassComponent>(entity).unwrap().mass;
+            let origin = (parent_motion.position, parent_motion.velocity);
+            (
+                origin,
+                orbit.map(|orbit| orbit.state(G * (parent_mass + mass))),
+            )
+        }
+        PlacementComponent::Lagrange {
+            primary,
+            secondary,
+            point,
+        } => {
+            let (primary, secondary) = (
+                find(world, entity, &primary)?,
+                find(world, entity, &secondary)?,
+            );
+            let points = pair_lagrange_points(world, primary, secondary).ok_or_else(|| {
+                PlacementError::NoLagrangePoints {
+                    planet: name_of(world, entity),
+                }
+            })?;
+            (points[point.index()], None)
+        }
+    };
+    let mut motion = world.get::<&mut Motion>(entity).unwrap();
+    let (position, velocity) = state.unwrap_or((motion.position, motion.velocity));
+    motion.position = vecmath::vec2_add(origin.0, position);
+    motion.velocity = vecmath::vec2_add(origin.1, velocity);
+    Ok(())
+}
+
+/// entity of the body the entity is placed relative to
+fn find(world: &World, entity: Entity, name: &str) -> Result<Entity, PlacementError> {
+    find_by_name(world, name).ok_or_else(|| PlacementError::UnknownBody {
+        planet: name_of(world, entity),
+        body: name.to_string(),
+    })
+}
+
+fn find_by_name(world: &World, name: &str) -> Option<Entity> {
+    world
+        .query::<&NameComponent>()
+        .iter()
+        .find(|(_id, n)| n.name == name)
+        .map(|(id, _)| id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(world: &mut World, name: &str, position: [f64; 2]) -> Entity {
+        world.spawn((
+            NameComponent::new(String::from(name)),
+            MassComponent::new(1.0e20),
+            Motion::position(position),
+        ))
+    }
+
+    #[test]
+    fn coinciding_pair_has_no_lagrange_points() {
+        let mut world = World::new();
+        body(&mut world, "Planet", [0.0, 0.0]);
+        body(&mut world, "Moon", [0.0, 0.0]);
+        let trojan = body(&mut world, "Trojan", [0.0, 0.0]);
+        world
+            .insert_one(
+                trojan,
+                PlacementComponent::lagrange(
+                    String::from("Planet"),
+                    String::from("Moon"),
+                    LagrangePoint::L4,
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            place(&mut world, trojan),
+            Err(PlacementError::NoLagrangePoints {
+                planet: String::from("Trojan")
+            })
+        );
+    }
+}
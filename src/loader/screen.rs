@@ -4,8 +4,8 @@ use opengl_graphics::{GlGraphics, GlyphCache};
 use piston::input::RenderArgs;
 
 use crate::core::gl::SharedGraphics;
+use crate::core::text::SharedGlyphCache;
 use crate::loader::state::LoadingState;
-use crate::text::SharedGlyphCache;
 
 pub struct LoadingScreen<'l> {
     gl: SharedGraphics,
@@ -3,6 +3,7 @@ use crate::core::gl::SharedGraphics;
 use crate::core::text::SharedGlyphCache;
 use crate::core::world::SharedWorld;
 use crate::loader::model_loader::{ModelLoader, ToEntityBuilder};
+use crate::loader::placement::PlacementError;
 use crate::loader::screen::LoadingScreen;
 use crate::loader::state::LoadingState;
 use piston::input::{Event, RenderEvent, UpdateEvent};
@@ -12,6 +13,7 @@ pub struct LoadingStage<'a> {
     screen: LoadingScreen<'a>,
     loader: ModelLoader<'a>,
     state: LoadingState,
+    error: Option<PlacementError>,
 }
 
 impl<'a> LoadingStage<'a> {
@@ -29,8 +31,14 @@ impl<'a> LoadingStage<'a> {
             screen,
             loader,
             state,
+            error: None,
         }
     }
+
+    /// error that stopped loading of the models
+    pub fn take_error(&mut self) -> Option<PlacementError> {
+        self.error.take()
+    }
 }
 
 impl<'a> EventHandler for LoadingStage<'a> {
@@ -44,7 +52,10 @@ impl<'a> EventHandler for LoadingStage<'a> {
             screen.render(state, args);
         }
         if e.update_args().is_some() {
-            loader.update(state, world);
+            if let Err(error) = loader.update(state, world) {
+                self.error = Some(error);
+                return true;
+            }
         }
 
         if self.state.done() {
@@ -1,22 +1,13 @@
 use clap::{arg, ArgMatches, Command};
-use opengl_graphics::OpenGL;
-use piston::event_loop::{EventSettings, Events};
-use piston::window::WindowSettings;
-
-use self::core::simulator;
-use crate::core::events::EventLoop;
-use crate::core::{gl, text, world};
-use crate::gl::ScreenResolution;
-use crate::loader::stage::LoadingStage;
-use crate::model::Simulation;
-use crate::simulation::SimulationStage;
-
-mod core;
-mod loader;
-mod model;
-mod physics;
-mod render;
-mod simulation;
+use std::str::FromStr;
+
+#[cfg(feature = "render")]
+use planets::core::gl::ScreenResolution;
+#[cfg(feature = "render")]
+use planets::core::simulator;
+use planets::headless;
+use planets::headless::HeadlessSettings;
+use planets::physics::integrator::Integrator;
 
 fn main() {
     let cli_matches = Command::new("planets")
@@ -33,13 +24,85 @@ fn main() {
                 .required(false)
                 .help("run in windowed mode"),
         )
+        .arg(
+            arg!(-i --integrator <INTEGRATOR>)
+                .required(false)
+                .value_parser(Integrator::from_str)
+                .global(true)
+                .help("override scenario integrator: euler, verlet, yoshida4, rk4 or dopri"),
+        )
+        .arg(
+            arg!(-d --diagnostics <FILE>)
+                .required(false)
+                .help("write energy and momentum of every frame to a csv file"),
+        )
+        .subcommand(
+            Command::new("headless")
+                .about("run simulation without a window and write states of bodies to a csv file")
+                .arg(
+                    arg!(--duration <SECONDS>)
+                        .required(true)
+                        .value_parser(positive_seconds)
+                        .help("simulated duration in seconds"),
+                )
+                .arg(
+                    arg!(--step <SECONDS>)
+                        .required(false)
+                        .default_value("60")
+                        .value_parser(positive_seconds)
+                        .help("physics step in simulated seconds"),
+                )
+                .arg(
+                    arg!(-o --output <FILE>)
+                        .required(true)
+                        .help("csv file for states of bodies"),
+                )
+                .arg(
+                    arg!(--"snapshot-interval" <SECONDS>)
+                        .required(false)
+                        .value_parser(positive_seconds)
+                        .help("write states of bodies every given simulated seconds"),
+                ),
+        )
         .get_matches();
 
+    let integrator = cli_matches.get_one::<Integrator>("integrator").copied();
+    if let Some(headless_matches) = cli_matches.subcommand_matches("headless") {
+        let settings = configure_headless(headless_matches);
+        if let Err(error) = headless::run("simulation", "assets", integrator, settings) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    run_windowed(cli_matches, integrator);
+}
+
+#[cfg(feature = "render")]
+fn run_windowed(cli_matches: ArgMatches, integrator: Option<Integrator>) {
+    let diagnostics_log = cli_matches.get_one::<String>("diagnostics").cloned();
     let resolution = configure_resolution(cli_matches);
 
-    simulator::run("simulation", "assets", resolution);
+    let result = simulator::run(
+        "simulation",
+        "assets",
+        resolution,
+        integrator,
+        diagnostics_log.as_deref(),
+    );
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
 }
 
+#[cfg(not(feature = "render"))]
+fn run_windowed(_cli_matches: ArgMatches, _integrator: Option<Integrator>) {
+    eprintln!("planets is built without the render feature. use the headless subcommand");
+    std::process::exit(1);
+}
+
+#[cfg(feature = "render")]
 fn configure_resolution(cli_matches: ArgMatches) -> ScreenResolution {
     let mut resolution = ScreenResolution::default();
     if cli_matches.get_flag("windowed") {
@@ -51,3 +114,22 @@ fn configure_resolution(cli_matches: ArgMatches) -> ScreenResolution {
     }
     resolution
 }
+
+fn configure_headless(cli_matches: &ArgMatches) -> HeadlessSettings {
+    HeadlessSettings {
+        duration: *cli_matches.get_one::<f64>("duration").unwrap(),
+        step: *cli_matches.get_one::<f64>("step").unwrap(),
+        output: cli_matches.get_one::<String>("output").unwrap().clone(),
+        snapshot_interval: cli_matches.get_one::<f64>("snapshot-interval").copied(),
+    }
+}
+
+/// number of simulated seconds that is finite and greater than zero
+fn positive_seconds(value: &str) -> Result<f64, String> {
+    let seconds = f64::from_str(value).map_err(|_| format!("{} is not a number", value))?;
+    if seconds.is_finite() && seconds > 0.0 {
+        Ok(seconds)
+    } else {
+        Err(format!("{} must be a positive number of seconds", value))
+    }
+}
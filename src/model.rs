@@ -1,28 +1,160 @@
+use crate::core::name::NameComponent;
+#[cfg(feature = "render")]
 use crate::core::texture::load_texture;
+#[cfg(feature = "render")]
 use crate::loader::model_loader::ToEntityBuilder;
+use crate::loader::placement::PlacementComponent;
+use crate::physics::collision::{CollisionPolicy, RadiusComponent};
 use crate::physics::force::ForceComponent;
-use crate::physics::gravity::{Mass, MassComponent};
+use crate::physics::gravity::{
+    center_on_barycenter, GravitySolver, Mass, MassComponent, SofteningComponent,
+};
+use crate::physics::integrator::Integrator;
+use crate::physics::lagrange::LagrangePoint;
 use crate::physics::motion::Motion;
+use crate::physics::orbit::{true_anomaly_from_mean, OrbitalElements};
+use crate::physics::universe::{Propagation, Timestep, Universe};
+#[cfg(feature = "render")]
 use crate::render::background::BackgroundComponent;
-use crate::render::name::NameComponent;
+#[cfg(feature = "render")]
+use crate::render::prediction::Prediction;
+#[cfg(feature = "render")]
 use crate::render::render_box::RenderBoxComponent;
+#[cfg(feature = "render")]
 use crate::render::sprite::Sprite;
+#[cfg(feature = "render")]
+use crate::render::trail::{Trail, TrailComponent};
 use assets_manager::{loader, Asset};
-use hecs::EntityBuilder;
+use hecs::{EntityBuilder, World};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// position of the body on its orbit
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub enum Anomaly {
+    /// true anomaly in degrees
+    True(f64),
+    /// mean anomaly in degrees
+    Mean(f64),
+}
+
+impl Default for Anomaly {
+    fn default() -> Self {
+        Anomaly::True(0.0)
+    }
+}
+
+/// keplerian orbit around the parent body. angles are in degrees
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct Orbit {
+    pub semi_major_axis: f64,
+    #[serde(default)]
+    pub eccentricity: f64,
+    #[serde(default)]
+    pub argument_of_periapsis: f64,
+    #[serde(default)]
+    pub anomaly: Anomaly,
+    #[serde(default)]
+    pub clockwise: bool,
+}
+
+impl Orbit {
+    /// why the orbit can't be placed. none for valid orbits
+    fn problem(&self) -> Option<&'static str> {
+        let (a, e) = (self.semi_major_axis, self.eccentricity);
+        let anomaly = match self.anomaly {
+            Anomaly::True(anomaly) | Anomaly::Mean(anomaly) => anomaly,
+        };
+        if !a.is_finite() || !e.is_finite() || !anomaly.is_finite() {
+            Some("elements must be finite numbers")
+        } else if e < 0.0 {
+            Some("eccentricity must not be negative")
+        } else if e == 1.0 {
+            Some("parabolic orbits are not supported")
+        } else if e < 1.0 && a <= 0.0 {
+            Some("closed orbits need a positive semi-major axis")
+        } else if e > 1.0 && a >= 0.0 {
+            Some("hyperbolic orbits need a negative semi-major axis")
+        } else if e > 1.0 && self.beyond_asymptote() {
+            Some("true anomaly is beyond the asymptote of the hyperbola")
+        } else {
+            None
+        }
+    }
+
+    /// true anomaly a hyperbola never reaches, where the radius would be negative
+    fn beyond_asymptote(&self) -> bool {
+        match self.anomaly {
+            Anomaly::True(anomaly) => {
+                // wrap into -180..180 degrees
+                let anomaly = (anomaly + 180.0).rem_euclid(360.0) - 180.0;
+                anomaly.to_radians().abs() >= (-1.0 / self.eccentricity).acos()
+            }
+            Anomaly::Mean(_) => false,
+        }
+    }
+
+    pub fn elements(&self) -> OrbitalElements {
+        let true_anomaly = match self.anomaly {
+            Anomaly::True(anomaly) => anomaly.to_radians(),
+            Anomaly::Mean(anomaly) => {
+                true_anomaly_from_mean(anomaly.to_radians(), self.eccentricity)
+            }
+        };
+        OrbitalElements {
+            semi_major_axis: self.semi_major_axis,
+            eccentricity: self.eccentricity,
+            argument_of_periapsis: self.argument_of_periapsis.to_radians(),
+            true_anomaly,
+            clockwise: self.clockwise,
+        }
+    }
+}
+
+/// lagrange point of a pair of bodies, e.g. l4 of kerbin and the mun
+#[derive(Clone, Debug, Deserialize)]
+pub struct Lagrange {
+    pub primary: String,
+    pub secondary: String,
+    pub point: LagrangePoint,
+}
 
 #[derive(Deserialize)]
 pub struct Planet {
+    #[serde(default)]
     pub position: (f64, f64),
+    #[serde(default)]
     pub velocity: (f64, f64),
+    /// name of the body this one orbits. position and velocity are relative to the parent
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// orbit around the parent. replaces position and velocity
+    #[serde(default)]
+    pub orbit: Option<Orbit>,
+    /// lagrange point the body is placed at instead of a parent. position and velocity are
+    /// relative to the point
+    #[serde(default)]
+    pub lagrange: Option<Lagrange>,
     pub name: String,
     pub mass: Mass,
     pub image: String,
     pub visible_radius: f64,
+    /// physical radius in meters. bodies without radius never collide
+    #[serde(default)]
+    pub radius: f64,
+    /// plummer softening length in meters. overrides the simulation softening
+    #[serde(default)]
+    pub softening: Option<f64>,
+    #[cfg(feature = "render")]
+    #[serde(default)]
+    pub trail: Trail,
 }
 
-impl ToEntityBuilder for Planet {
-    fn to_entity_builder(&self) -> EntityBuilder {
+impl Planet {
+    /// builder with physical components only. it doesn't touch textures
+    pub fn body_builder(&self) -> EntityBuilder {
         let mut builder = EntityBuilder::new();
         let position = [self.position.0, self.position.1];
         let velocity = [self.velocity.0, self.velocity.1];
@@ -31,10 +163,47 @@ impl ToEntityBuilder for Planet {
             .add(MassComponent::new(self.mass))
             .add(Motion::new_position_velocity(position, velocity))
             .add(ForceComponent::zero())
+            .add(RadiusComponent::new(self.radius));
+        if let Some(softening) = self.softening {
+            builder.add(SofteningComponent::new(softening));
+        }
+        if let Some(parent) = &self.parent {
+            let orbit = self.orbit.map(|orbit| orbit.elements());
+            builder.add(PlacementComponent::new(parent.clone(), orbit));
+        }
+        if let Some(lagrange) = &self.lagrange {
+            builder.add(PlacementComponent::lagrange(
+                lagrange.primary.clone(),
+                lagrange.secondary.clone(),
+                lagrange.point,
+            ));
+        }
+        builder
+    }
+
+    /// names of bodies the planet is placed relative to
+    fn references(&self) -> Vec<&str> {
+        let mut references: Vec<&str> = self.parent.iter().map(String::as_str).collect();
+        if let Some(lagrange) = &self.lagrange {
+            references.push(lagrange.primary.as_str());
+            references.push(lagrange.secondary.as_str());
+        }
+        references
+    }
+}
+
+#[cfg(feature = "render")]
+impl ToEntityBuilder for Planet {
+    fn to_entity_builder(&self) -> EntityBuilder {
+        let mut builder = self.body_builder();
+        builder
             .add(RenderBoxComponent::centered_square(self.visible_radius))
             .add(Sprite::image(load_texture(String::from(
                 self.image.as_str(),
             ))));
+        if self.trail.enabled {
+            builder.add(TrailComponent::new(self.trail));
+        }
         builder
     }
 }
@@ -54,6 +223,7 @@ impl Asset for Background {
     type Loader = loader::RonLoader;
 }
 
+#[cfg(feature = "render")]
 impl ToEntityBuilder for Background {
     fn to_entity_builder(&self) -> EntityBuilder {
         let image_path = String::from(self.image.as_str());
@@ -63,10 +233,139 @@ impl ToEntityBuilder for Background {
     }
 }
 
+/// planets of a simulation that can't be placed
+#[derive(Debug, PartialEq)]
+pub enum HierarchyError {
+    UnknownParent {
+        planet: String,
+        parent: String,
+    },
+    /// primary or secondary of the lagrange point of the planet
+    UnknownLagrangeBody {
+        planet: String,
+        body: String,
+    },
+    OrbitWithoutParent {
+        planet: String,
+    },
+    InvalidOrbit {
+        planet: String,
+        reason: &'static str,
+    },
+    ParentAndLagrangePoint {
+        planet: String,
+    },
+    /// name shared by several planets
+    DuplicateName(String),
+    /// names of planets in the cycle, starting and ending with the same planet
+    Cycle(Vec<String>),
+}
+
+impl Display for HierarchyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HierarchyError::UnknownParent { planet, parent } => {
+                write!(f, "unknown parent {} of {}", parent, planet)
+            }
+            HierarchyError::UnknownLagrangeBody { planet, body } => {
+                write!(
+                    f,
+                    "unknown body {} of the lagrange point of {}",
+                    body, planet
+                )
+            }
+            HierarchyError::OrbitWithoutParent { planet } => {
+                write!(f, "orbit of {} needs a parent", planet)
+            }
+            HierarchyError::InvalidOrbit { planet, reason } => {
+                write!(f, "invalid orbit of {}: {}", planet, reason)
+            }
+            HierarchyError::ParentAndLagrangePoint { planet } => {
+                write!(f, "{} has both a parent and a lagrange point", planet)
+            }
+            HierarchyError::DuplicateName(name) => {
+                write!(f, "several planets are named {}", name)
+            }
+            HierarchyError::Cycle(names) => write!(f, "cycle of parents {}", names.join(" -> ")),
+        }
+    }
+}
+
+impl Error for HierarchyError {}
+
+/// settings of a simulation it can't run with
+#[derive(Debug, PartialEq)]
+pub enum SettingsError {
+    InvalidTimestep(Timestep),
+    /// tolerance of the adaptive integrator
+    InvalidTolerance(f64),
+    #[cfg(feature = "render")]
+    InvalidTrailLength {
+        planet: String,
+        length: f64,
+    },
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::InvalidTimestep(timestep) => write!(
+                f,
+                "invalid timestep {:?}. max_step must be positive and max_steps at least 1",
+                timestep
+            ),
+            SettingsError::InvalidTolerance(tolerance) => write!(
+                f,
+                "invalid tolerance {} of the integrator. it must be positive",
+                tolerance
+            ),
+            #[cfg(feature = "render")]
+            SettingsError::InvalidTrailLength { planet, length } => write!(
+                f,
+                "invalid trail length {} of {}. it must be positive",
+                length, planet
+            ),
+        }
+    }
+}
+
+impl Error for SettingsError {}
+
 #[derive(Deserialize)]
 pub struct Simulation {
     planets: Vec<Planet>,
     background: Background,
+    #[serde(default)]
+    integrator: Integrator,
+    #[serde(default)]
+    timestep: Timestep,
+    #[serde(default)]
+    propagation: Propagation,
+    #[serde(default)]
+    gravity: GravitySolver,
+    #[serde(default)]
+    collisions: CollisionPolicy,
+    /// plummer softening length in meters
+    #[serde(default)]
+    softening: f64,
+    /// bodies closer than this distance in meters are logged as encounters
+    #[serde(default)]
+    encounter_distance: f64,
+    /// show drift of conserved quantities
+    #[serde(default)]
+    diagnostics: bool,
+    /// log bodies crossing spheres of influence
+    #[serde(default)]
+    spheres_of_influence: bool,
+    /// relative drift of conserved quantities that pauses the simulation
+    #[serde(default)]
+    drift_threshold: f64,
+    /// move the center of mass to the origin and remove net momentum once bodies are placed
+    #[serde(default)]
+    barycentric: bool,
+    #[cfg(feature = "render")]
+    #[serde(default)]
+    prediction: Prediction,
 }
 
 impl Asset for Simulation {
@@ -75,9 +374,363 @@ impl Asset for Simulation {
 }
 
 impl Simulation {
-    pub fn models(&self) -> Vec<&dyn ToEntityBuilder> {
+    #[cfg(feature = "render")]
+    pub fn models(&self) -> Result<Vec<&dyn ToEntityBuilder>, HierarchyError> {
         let mut models: Vec<&dyn ToEntityBuilder> = vec![&self.background];
-        self.planets.iter().for_each(|planet| models.push(planet));
-        models
+        for planet in self.planets()? {
+            models.push(planet);
+        }
+        Ok(models)
+    }
+
+    /// settings of predicted orbit paths
+    #[cfg(feature = "render")]
+    pub fn prediction(&self) -> Prediction {
+        self.prediction
+    }
+
+    /// planets ordered so that every parent comes before its children
+    ///
+    /// bodies of lagrange points come before planets placed at them.
+    pub fn planets(&self) -> Result<Vec<&Planet>, HierarchyError> {
+        let mut by_name: HashMap<&str, &Planet> = HashMap::with_capacity(self.planets.len());
+        for planet in self.planets.iter() {
+            if by_name.insert(planet.name.as_str(), planet).is_some() {
+                return Err(HierarchyError::DuplicateName(planet.name.clone()));
+            }
+        }
+        let mut depths = HashMap::with_capacity(self.planets.len());
+        let mut ordered = Vec::with_capacity(self.planets.len());
+        for planet in self.planets.iter() {
+            if planet.orbit.is_some() && planet.parent.is_none() {
+                return Err(HierarchyError::OrbitWithoutParent {
+                    planet: planet.name.clone(),
+                });
+            }
+            if let Some(reason) = planet.orbit.as_ref().and_then(Orbit::problem) {
+                return Err(HierarchyError::InvalidOrbit {
+                    planet: planet.name.clone(),
+                    reason,
+                });
+            }
+            if planet.parent.is_some() && planet.lagrange.is_some() {
+                return Err(HierarchyError::ParentAndLagrangePoint {
+                    planet: planet.name.clone(),
+                });
+            }
+            let depth = depth(planet, &by_name, &mut depths, &mut vec![])?;
+            ordered.push((depth, planet));
+        }
+        ordered.sort_by_key(|(depth, _)| *depth);
+        Ok(ordered.into_iter().map(|(_, planet)| planet).collect())
+    }
+
+    /// check settings that can be read but make no sense for the simulation
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !self.timestep.is_valid() {
+            return Err(SettingsError::InvalidTimestep(self.timestep));
+        }
+        if let Integrator::DormandPrince { tolerance } = self.integrator {
+            if !(tolerance.is_finite() && tolerance > 0.0) {
+                return Err(SettingsError::InvalidTolerance(tolerance));
+            }
+        }
+        #[cfg(feature = "render")]
+        for planet in self.planets.iter() {
+            let length = planet.trail.length;
+            if !(length.is_finite() && length > 0.0) {
+                return Err(SettingsError::InvalidTrailLength {
+                    planet: planet.name.clone(),
+                    length,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    /// universe configured with physics settings of the simulation
+    pub fn universe(&self) -> Universe {
+        let mut universe = Universe::default();
+        universe.set_integrator(self.integrator);
+        universe.set_timestep(self.timestep);
+        universe.set_propagation(self.propagation);
+        universe.set_gravity_solver(self.gravity);
+        universe.set_collision_policy(self.collisions);
+        universe.set_softening(self.softening);
+        universe.set_encounter_distance(self.encounter_distance);
+        universe.set_spheres_of_influence(self.spheres_of_influence);
+        universe.set_diagnostics(self.diagnostics);
+        universe.set_drift_threshold(self.drift_threshold);
+        universe
+    }
+
+    /// shift placed bodies so the center of mass rests in the origin, if the simulation asks to
+    pub fn center_bodies(&self, world: &mut World) {
+        if self.barycentric {
+            center_on_barycenter(world);
+        }
+    }
+}
+
+/// length of the longest chain of references from the planet, counting the planet itself
+///
+/// chain holds names of planets being measured, so a planet found in it closes a cycle.
+fn depth<'a>(
+    planet: &'a Planet,
+    by_name: &HashMap<&str, &'a Planet>,
+    depths: &mut HashMap<&'a str, usize>,
+    chain: &mut Vec<&'a str>,
+) -> Result<usize, HierarchyError> {
+    if let Some(depth) = depths.get(planet.name.as_str()) {
+        return Ok(*depth);
+    }
+    if let Some(start) = chain.iter().position(|name| *name == planet.name) {
+        let mut cycle: Vec<String> = chain[start..].iter().map(|name| name.to_string()).collect();
+        cycle.push(planet.name.clone());
+        return Err(HierarchyError::Cycle(cycle));
+    }
+    chain.push(planet.name.as_str());
+    let mut planet_depth = 1;
+    for reference in planet.references() {
+        // a planet with a parent is never placed at a lagrange point
+        let parent = by_name.get(reference).copied().ok_or_else(|| {
+            if planet.parent.is_some() {
+                HierarchyError::UnknownParent {
+                    planet: planet.name.clone(),
+                    parent: reference.to_string(),
+                }
+            } else {
+                HierarchyError::UnknownLagrangeBody {
+                    planet: planet.name.clone(),
+                    body: reference.to_string(),
+                }
+            }
+        })?;
+        planet_depth = planet_depth.max(depth(parent, by_name, depths, chain)? + 1);
+    }
+    chain.pop();
+    depths.insert(planet.name.as_str(), planet_depth);
+    Ok(planet_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planet(name: &str, parent: Option<&str>) -> Planet {
+        Planet {
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            parent: parent.map(String::from),
+            orbit: None,
+            lagrange: None,
+            name: String::from(name),
+            mass: 1.0,
+            image: String::from(name),
+            visible_radius: 1.0,
+            radius: 0.0,
+            softening: None,
+            #[cfg(feature = "render")]
+            trail: Trail::default(),
+        }
+    }
+
+    fn simulation(planets: Vec<Planet>) -> Simulation {
+        Simulation {
+            planets,
+            background: Background {
+                image: String::from("background"),
+            },
+            integrator: Integrator::default(),
+            timestep: Timestep::default(),
+            propagation: Propagation::default(),
+            gravity: GravitySolver::default(),
+            collisions: CollisionPolicy::default(),
+            softening: 0.0,
+            encounter_distance: 0.0,
+            diagnostics: false,
+            spheres_of_influence: false,
+            drift_threshold: 0.0,
+            barycentric: false,
+            #[cfg(feature = "render")]
+            prediction: Prediction::default(),
+        }
+    }
+
+    fn names(simulation: &Simulation) -> Vec<&str> {
+        let planets = simulation.planets().unwrap();
+        planets.iter().map(|planet| planet.name.as_str()).collect()
+    }
+
+    #[test]
+    fn parents_come_before_children() {
+        let simulation = simulation(vec![
+            planet("Moon", Some("Planet")),
+            planet("Planet", Some("Star")),
+            planet("Star", None),
+            planet("Comet", None),
+        ]);
+        assert_eq!(names(&simulation), vec!["Star", "Comet", "Planet", "Moon"]);
+    }
+
+    #[test]
+    fn unknown_parent() {
+        let simulation = simulation(vec![planet("Star", None), planet("Moon", Some("Planet"))]);
+        assert_eq!(
+            simulation.planets().err(),
+            Some(HierarchyError::UnknownParent {
+                planet: String::from("Moon"),
+                parent: String::from("Planet"),
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let simulation = simulation(vec![
+            planet("Star", None),
+            planet("Moon", Some("Star")),
+            planet("Star", None),
+        ]);
+        assert_eq!(
+            simulation.planets().err(),
+            Some(HierarchyError::DuplicateName(String::from("Star")))
+        );
+    }
+
+    #[test]
+    fn cycle_of_parents() {
+        let simulation = simulation(vec![
+            planet("Moon", Some("Planet")),
+            planet("Planet", Some("Moon")),
+        ]);
+        let error = simulation.planets().err().unwrap();
+        assert_eq!(error.to_string(), "cycle of parents Moon -> Planet -> Moon");
+    }
+
+    #[test]
+    fn invalid_orbits() {
+        let orbit = |semi_major_axis, eccentricity| Orbit {
+            semi_major_axis,
+            eccentricity,
+            argument_of_periapsis: 0.0,
+            anomaly: Anomaly::default(),
+            clockwise: false,
+        };
+        let error = |orbit: Orbit| {
+            let mut moon = planet("Moon", Some("Planet"));
+            moon.orbit = Some(orbit);
+            let simulation = simulation(vec![planet("Planet", None), moon]);
+            simulation.planets().err().map(|error| error.to_string())
+        };
+        assert_eq!(error(orbit(1.0e7, 0.5)), None);
+        assert_eq!(error(orbit(-1.0e7, 1.5)), None);
+        assert_eq!(
+            error(orbit(1.0e7, 1.0)),
+            Some(String::from(
+                "invalid orbit of Moon: parabolic orbits are not supported"
+            ))
+        );
+        assert!(error(orbit(1.0e7, -0.1)).is_some());
+        assert!(error(orbit(-1.0e7, 0.5)).is_some());
+        assert!(error(orbit(1.0e7, 1.5)).is_some());
+        assert!(error(orbit(f64::NAN, 0.5)).is_some());
+
+        let hyperbola = |anomaly| Orbit {
+            anomaly: Anomaly::True(anomaly),
+            ..orbit(-1.0e7, 1.5)
+        };
+        assert_eq!(error(hyperbola(30.0)), None);
+        assert_eq!(
+            error(hyperbola(180.0)),
+            Some(String::from(
+                "invalid orbit of Moon: true anomaly is beyond the asymptote of the hyperbola"
+            ))
+        );
+        // the asymptote of e = 1.5 is at 131.8 degrees
+        assert!(error(hyperbola(-135.0)).is_some());
+        assert!(error(hyperbola(225.0)).is_some());
+        assert_eq!(error(hyperbola(-130.0)), None);
+    }
+
+    #[test]
+    fn zero_fixed_step() {
+        let mut simulation = simulation(vec![planet("Star", None)]);
+        assert_eq!(simulation.validate(), Ok(()));
+        simulation.timestep = Timestep::Fixed {
+            max_step: 0.0,
+            max_steps: 500,
+        };
+        assert_eq!(
+            simulation.validate(),
+            Err(SettingsError::InvalidTimestep(simulation.timestep))
+        );
+    }
+
+    #[test]
+    fn zero_tolerance() {
+        let mut simulation = simulation(vec![planet("Star", None)]);
+        simulation.integrator = Integrator::DormandPrince { tolerance: 1.0e-9 };
+        assert_eq!(simulation.validate(), Ok(()));
+        for tolerance in [0.0, -1.0e-9, f64::INFINITY] {
+            simulation.integrator = Integrator::DormandPrince { tolerance };
+            assert_eq!(
+                simulation.validate(),
+                Err(SettingsError::InvalidTolerance(tolerance))
+            );
+        }
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn zero_trail_length() {
+        let mut star = planet("Star", None);
+        star.trail.length = 0.0;
+        let simulation = simulation(vec![star]);
+        assert_eq!(
+            simulation.validate(),
+            Err(SettingsError::InvalidTrailLength {
+                planet: String::from("Star"),
+                length: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_lagrange_body() {
+        let mut trojan = planet("Trojan", None);
+        trojan.lagrange = Some(Lagrange {
+            primary: String::from("Planet"),
+            secondary: String::from("Moon"),
+            point: LagrangePoint::L5,
+        });
+        let simulation = simulation(vec![planet("Planet", None), trojan]);
+        assert_eq!(
+            simulation.planets().err(),
+            Some(HierarchyError::UnknownLagrangeBody {
+                planet: String::from("Trojan"),
+                body: String::from("Moon"),
+            })
+        );
+    }
+
+    #[test]
+    fn lagrange_pair_comes_before_trojans() {
+        let mut trojan = planet("Trojan", None);
+        trojan.lagrange = Some(Lagrange {
+            primary: String::from("Planet"),
+            secondary: String::from("Moon"),
+            point: LagrangePoint::L4,
+        });
+        let simulation = simulation(vec![
+            trojan,
+            planet("Planet", None),
+            planet("Moon", Some("Planet")),
+        ]);
+        assert_eq!(names(&simulation), vec!["Planet", "Moon", "Trojan"]);
     }
 }
@@ -0,0 +1,163 @@
+use crate::physics::force::Force;
+use crate::physics::gravity::{attraction, GravityCalculation, Mass};
+use crate::physics::motion::{distance_between, Position};
+
+/// bodies deeper than this share a leaf instead of splitting it further
+const MAX_DEPTH: usize = 48;
+
+struct Node {
+    center: Position,
+    half_size: f64,
+    mass: Mass,
+    mass_center: Position,
+    /// index of the first of four children
+    children: Option<usize>,
+    bodies: Vec<usize>,
+}
+
+impl Node {
+    fn new(center: Position, half_size: f64) -> Self {
+        Node {
+            center,
+            half_size,
+            mass: 0.0,
+            mass_center: [0.0, 0.0],
+            children: None,
+            bodies: vec![],
+        }
+    }
+
+    /// accumulate mass. mass center holds a mass-weighted sum until the tree is built
+    fn add_mass(&mut self, mass: Mass, position: Position) {
+        self.mass += mass;
+        self.mass_center = vecmath::vec2_add(self.mass_center, vecmath::vec2_scale(position, mass));
+    }
+
+    fn quadrant(&self, position: Position) -> usize {
+        let right = (position[0] >= self.center[0]) as usize;
+        let top = (position[1] >= self.center[1]) as usize;
+        right + 2 * top
+    }
+
+    fn contains(&self, position: Position) -> bool {
+        (position[0] - self.center[0]).abs() <= self.half_size
+            && (position[1] - self.center[1]).abs() <= self.half_size
+    }
+}
+
+/// quadtree of bodies with masses and mass centers of every cell
+pub struct QuadTree {
+    nodes: Vec<Node>,
+}
+
+impl QuadTree {
+    pub fn build(bodies: &[GravityCalculation]) -> Self {
+        let mut min = [f64::MAX, f64::MAX];
+        let mut max = [f64::MIN, f64::MIN];
+        for body in bodies {
+            min = [min[0].min(body.position[0]), min[1].min(body.position[1])];
+            max = [max[0].max(body.position[0]), max[1].max(body.position[1])];
+        }
+        let center = vecmath::vec2_scale(vecmath::vec2_add(min, max), 0.5);
+        let half_size = f64::max(max[0] - min[0], max[1] - min[1]) / 2.0;
+
+        let mut tree = QuadTree {
+            nodes: vec![Node::new(center, half_size)],
+        };
+        if bodies.is_empty() {
+            return tree;
+        }
+        for i in 0..bodies.len() {
+            tree.insert(i, bodies);
+        }
+        for node in tree.nodes.iter_mut() {
+            if node.mass > 0.0 {
+                node.mass_center = vecmath::vec2_scale(node.mass_center, 1.0 / node.mass);
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, body: usize, bodies: &[GravityCalculation]) {
+        let GravityCalculation { mass, position, .. } = bodies[body];
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            self.nodes[node].add_mass(mass, position);
+            if let Some(first) = self.nodes[node].children {
+                node = first + self.nodes[node].quadrant(position);
+                depth += 1;
+                continue;
+            }
+            if self.nodes[node].bodies.is_empty() || depth >= MAX_DEPTH {
+                self.nodes[node].bodies.push(body);
+                return;
+            }
+            // occupied leaf: split it and move its body one level down
+            let first = self.split(node);
+            let resident = self.nodes[node].bodies.pop().unwrap();
+            let resident_position = bodies[resident].position;
+            let resident_node = first + self.nodes[node].quadrant(resident_position);
+            self.nodes[resident_node].add_mass(bodies[resident].mass, resident_position);
+            self.nodes[resident_node].bodies.push(resident);
+
+            node = first + self.nodes[node].quadrant(position);
+            depth += 1;
+        }
+    }
+
+    fn split(&mut self, node: usize) -> usize {
+        let first = self.nodes.len();
+        let [x, y] = self.nodes[node].center;
+        let half_size = self.nodes[node].half_size / 2.0;
+        // order matches Node::quadrant
+        for center in [
+            [x - half_size, y - half_size],
+            [x + half_size, y - half_size],
+            [x - half_size, y + half_size],
+            [x + half_size, y + half_size],
+        ] {
+            self.nodes.push(Node::new(center, half_size));
+        }
+        self.nodes[node].children = Some(first);
+        first
+    }
+
+    /// gravitational force acting on the body with the index
+    pub fn force(&self, bodies: &[GravityCalculation], index: usize, opening_angle: f64) -> Force {
+        let body = &bodies[index];
+        let mut force: Force = [0.0, 0.0];
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if node.mass == 0.0 {
+                continue;
+            }
+            match node.children {
+                None => {
+                    for &other in node.bodies.iter().filter(|&&other| other != index) {
+                        force = vecmath::vec2_add(force, body.acceleration(&bodies[other]));
+                    }
+                }
+                Some(first) => {
+                    let distance = distance_between(&body.position, &node.mass_center);
+                    let size = node.half_size * 2.0;
+                    if !node.contains(body.position) && size < opening_angle * distance {
+                        // cells are softened with the softening of the body itself
+                        let cell_force = attraction(
+                            body.mass,
+                            body.position,
+                            node.mass,
+                            node.mass_center,
+                            body.softening * body.softening,
+                        );
+                        force = vecmath::vec2_add(force, cell_force);
+                    } else {
+                        stack.extend(first..first + 4);
+                    }
+                }
+            }
+        }
+        force
+    }
+}
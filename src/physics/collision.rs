@@ -0,0 +1,303 @@
+use crate::core::name::NameComponent;
+use crate::core::tracking::TrackingComponent;
+use crate::physics::gravity::{Mass, MassComponent};
+use crate::physics::motion::{distance_between, Motion, Position, Velocity};
+use hecs::{Entity, World};
+use serde::Deserialize;
+
+/// physical radius of the body used to detect collisions
+pub struct RadiusComponent {
+    pub radius: f64,
+}
+
+impl RadiusComponent {
+    pub fn new(radius: f64) -> Self {
+        RadiusComponent { radius }
+    }
+}
+
+/// what happens when two bodies touch each other
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum CollisionPolicy {
+    /// bodies pass through each other
+    #[default]
+    Ignore,
+    /// perfectly inelastic collision. the lighter body is absorbed by the heavier one
+    Merge,
+    /// perfectly elastic collision. bodies bounce off each other
+    Bounce,
+}
+
+#[derive(Copy, Clone)]
+struct Body {
+    entity: Entity,
+    mass: Mass,
+    radius: f64,
+    position: Position,
+    velocity: Velocity,
+    absorbed_by: Option<Entity>,
+}
+
+impl Body {
+    fn absorbed(&self) -> bool {
+        self.absorbed_by.is_some()
+    }
+
+    fn touches(&self, other: &Body) -> bool {
+        let reach = self.radius + other.radius;
+        reach > 0.0 && distance_between(&self.position, &other.position) < reach
+    }
+
+    /// absorb the other body conserving mass, momentum and volume
+    fn absorb(&mut self, other: &mut Body) {
+        let mass = self.mass + other.mass;
+        let weighted = |lhs: [f64; 2], rhs: [f64; 2]| {
+            vecmath::vec2_scale(
+                vecmath::vec2_add(
+                    vecmath::vec2_scale(lhs, self.mass),
+                    vecmath::vec2_scale(rhs, other.mass),
+                ),
+                1.0 / mass,
+            )
+        };
+        self.position = weighted(self.position, other.position);
+        self.velocity = weighted(self.velocity, other.velocity);
+        self.radius = (self.radius.powi(3) + other.radius.powi(3)).cbrt();
+        self.mass = mass;
+        other.absorbed_by = Some(self.entity);
+    }
+
+    /// exchange momentum along the line of centers and push bodies apart
+    fn bounce(&mut self, other: &mut Body) {
+        let separation = vecmath::vec2_sub(self.position, other.position);
+        let distance = vecmath::vec2_len(separation);
+        if distance == 0.0 {
+            return;
+        }
+        let normal = vecmath::vec2_scale(separation, 1.0 / distance);
+        let mass = self.mass + other.mass;
+
+        let overlap = self.radius + other.radius - distance;
+        self.position = vecmath::vec2_add(
+            self.position,
+            vecmath::vec2_scale(normal, overlap * other.mass / mass),
+        );
+        other.position = vecmath::vec2_sub(
+            other.position,
+            vecmath::vec2_scale(normal, overlap * self.mass / mass),
+        );
+
+        let approach = vecmath::vec2_dot(vecmath::vec2_sub(self.velocity, other.velocity), normal);
+        if approach >= 0.0 {
+            // already moving apart
+            return;
+        }
+        let impulse = 2.0 * approach / mass;
+        self.velocity = vecmath::vec2_sub(
+            self.velocity,
+            vecmath::vec2_scale(normal, impulse * other.mass),
+        );
+        other.velocity = vecmath::vec2_add(
+            other.velocity,
+            vecmath::vec2_scale(normal, impulse * self.mass),
+        );
+    }
+}
+
+/// detects touching bodies and resolves collisions according to the policy
+pub struct CollisionSystem {
+    policy: CollisionPolicy,
+}
+
+impl Default for CollisionSystem {
+    fn default() -> Self {
+        CollisionSystem::new()
+    }
+}
+
+impl CollisionSystem {
+    pub fn new() -> Self {
+        CollisionSystem {
+            policy: CollisionPolicy::default(),
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: CollisionPolicy) {
+        self.policy = policy;
+    }
+
+    /// resolve collisions of touching bodies
+    ///
+    /// returns true when bodies collided and their state changed
+    pub fn update(&mut self, world: &mut World) -> bool {
+        if self.policy == CollisionPolicy::Ignore {
+            return false;
+        }
+        let mut bodies: Vec<Body> = vec![];
+        for (id, (mass, radius, motion)) in
+            &mut world.query::<(&MassComponent, &RadiusComponent, &Motion)>()
+        {
+            bodies.push(Body {
+                entity: id,
+                mass: mass.mass,
+                radius: radius.radius,
+                position: motion.position,
+                velocity: motion.velocity,
+                absorbed_by: None,
+            });
+        }
+
+        // sweep along x axis: only bodies with overlapping x ranges may touch
+        bodies.sort_by(|a, b| (a.position[0] - a.radius).total_cmp(&(b.position[0] - b.radius)));
+        let mut collided = false;
+        for i in 0..bodies.len() {
+            for j in i + 1..bodies.len() {
+                // a body grows and moves when it absorbs another one
+                let right_edge = bodies[i].position[0] + bodies[i].radius;
+                if bodies[j].position[0] - bodies[j].radius > right_edge {
+                    break;
+                }
+                if bodies[i].absorbed() || bodies[j].absorbed() || !bodies[i].touches(&bodies[j]) {
+                    continue;
+                }
+                collided = true;
+                let (head, tail) = bodies.split_at_mut(j);
+                let (a, b) = (&mut head[i], &mut tail[0]);
+                match self.policy {
+                    CollisionPolicy::Merge if a.mass >= b.mass => {
+                        merge_names(world, a.entity, b.entity);
+                        a.absorb(b);
+                    }
+                    CollisionPolicy::Merge => {
+                        merge_names(world, b.entity, a.entity);
+                        b.absorb(a);
+                    }
+                    CollisionPolicy::Bounce => a.bounce(b),
+                    CollisionPolicy::Ignore => {}
+                }
+            }
+        }
+        if collided {
+            self.apply(world, &bodies);
+        }
+        collided
+    }
+
+    fn apply(&self, world: &mut World, bodies: &[Body]) {
+        for body in bodies.iter().filter(|body| body.absorbed()) {
+            let tracked = world.get::<&TrackingComponent>(body.entity).is_ok();
+            world
+                .despawn(body.entity)
+                .expect("can't despawn absorbed body");
+            if tracked {
+                // follow the body that absorbed the tracked one
+                let survivor = survivor(bodies, body);
+                world
+                    .insert_one(survivor, TrackingComponent::default())
+                    .unwrap();
+            }
+        }
+        for body in bodies.iter().filter(|body| !body.absorbed()) {
+            if let Ok((mass, radius, motion)) =
+                world.query_one_mut::<(&mut MassComponent, &mut RadiusComponent, &mut Motion)>(
+                    body.entity,
+                )
+            {
+                mass.mass = body.mass;
+                radius.radius = body.radius;
+                motion.position = body.position;
+                motion.velocity = body.velocity;
+            }
+        }
+    }
+}
+
+/// follow the chain of absorptions to the body that is left in the world
+fn survivor(bodies: &[Body], body: &Body) -> Entity {
+    let mut current = body;
+    while let Some(absorber) = current.absorbed_by {
+        current = bodies.iter().find(|b| b.entity == absorber).unwrap();
+    }
+    current.entity
+}
+
+/// give the survivor name of both bodies
+fn merge_names(world: &mut World, survivor: Entity, absorbed: Entity) {
+    let absorbed_name = match world.get::<&NameComponent>(absorbed) {
+        Ok(name) => name.name.clone(),
+        Err(_) => return,
+    };
+    if let Ok(mut name) = world.get::<&mut NameComponent>(survivor) {
+        name.name = format!("{}+{}", name.name, absorbed_name);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(world: &mut World, mass: Mass, position: Position, velocity: Velocity) -> Entity {
+        world.spawn((
+            MassComponent::new(mass),
+            RadiusComponent::new(1.0),
+            Motion::new_position_velocity(position, velocity),
+        ))
+    }
+
+    #[test]
+    fn merge_conserves_mass_and_momentum() {
+        let mut world = World::new();
+        let heavy = spawn(&mut world, 3.0, [0.0, 0.0], [1.0, 0.0]);
+        let light = spawn(&mut world, 1.0, [1.0, 0.0], [-1.0, 2.0]);
+        world
+            .insert_one(light, TrackingComponent::default())
+            .unwrap();
+
+        let mut collisions = CollisionSystem::new();
+        collisions.set_policy(CollisionPolicy::Merge);
+        collisions.update(&mut world);
+
+        assert!(!world.contains(light));
+        assert!(world.get::<&TrackingComponent>(heavy).is_ok());
+        assert_eq!(world.get::<&MassComponent>(heavy).unwrap().mass, 4.0);
+        let motion = world.get::<&Motion>(heavy).unwrap();
+        assert_eq!(motion.velocity, [0.5, 0.5]);
+        assert_eq!(motion.position, [0.25, 0.0]);
+    }
+
+    #[test]
+    fn bounce_exchanges_velocities_of_equal_bodies() {
+        let mut world = World::new();
+        let left = spawn(&mut world, 1.0, [0.0, 0.0], [1.0, 0.0]);
+        let right = spawn(&mut world, 1.0, [1.5, 0.0], [-1.0, 0.0]);
+
+        let mut collisions = CollisionSystem::new();
+        collisions.set_policy(CollisionPolicy::Bounce);
+        collisions.update(&mut world);
+
+        assert_eq!(world.get::<&Motion>(left).unwrap().velocity, [-1.0, 0.0]);
+        assert_eq!(world.get::<&Motion>(right).unwrap().velocity, [1.0, 0.0]);
+        let left_position = world.get::<&Motion>(left).unwrap().position;
+        let right_position = world.get::<&Motion>(right).unwrap().position;
+        assert!(distance_between(&left_position, &right_position) >= 2.0);
+    }
+
+    #[test]
+    fn grown_body_absorbs_bodies_beyond_its_old_edge() {
+        let mut world = World::new();
+        let heavy = spawn(&mut world, 8.0, [0.0, 0.0], [0.0, 0.0]);
+        spawn(&mut world, 8.0, [1.5, 0.0], [0.0, 0.0]);
+        let far = world.spawn((
+            MassComponent::new(1.0),
+            RadiusComponent::new(0.1),
+            Motion::position([2.0, 0.0]),
+        ));
+
+        let mut collisions = CollisionSystem::new();
+        collisions.set_policy(CollisionPolicy::Merge);
+        collisions.update(&mut world);
+
+        assert!(!world.contains(far));
+        assert_eq!(world.get::<&MassComponent>(heavy).unwrap().mass, 17.0);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::physics::gravity::{MassComponent, G};
+use crate::physics::motion::{Acceleration, Motion, Position, Velocity};
+use crate::physics::orbit::OrbitalElements;
+use crate::physics::soi;
+use hecs::{Entity, World};
+
+/// moves bodies along kepler orbits around the primaries of their spheres of influence
+///
+/// orbits are propagated analytically, so a step of any length is exact as long as the body
+/// stays around the same primary. bodies switch primaries when they cross spheres of influence
+/// between steps, so long steps notice crossings late.
+/// nearly parabolic orbits and bodies at the center of their primary are moved
+/// by a semi-implicit euler step instead.
+/// the heaviest body drifts with a constant velocity.
+pub struct PatchedConicsSystem {
+    step_size: f64,
+}
+
+impl Default for PatchedConicsSystem {
+    fn default() -> Self {
+        PatchedConicsSystem::new()
+    }
+}
+
+impl PatchedConicsSystem {
+    pub fn new() -> Self {
+        PatchedConicsSystem { step_size: 0.0 }
+    }
+
+    /// size of the last step in simulated seconds
+    pub fn step_size(&self) -> f64 {
+        self.step_size
+    }
+
+    pub fn update(&mut self, world: &mut World, dt: f64) {
+        self.step_size = dt;
+        let mut ids: Vec<Entity> = vec![];
+        let mut motions: Vec<Motion> = vec![];
+        let mut bodies = vec![];
+        for (id, (mass, motion)) in &mut world.query::<(&MassComponent, &Motion)>() {
+            ids.push(id);
+            motions.push(*motion);
+            bodies.push((mass.mass, motion.position));
+        }
+
+        let spheres = soi::spheres_of_influence(&bodies);
+        let mut moved = motions.clone();
+        for i in soi::by_mass(&bodies) {
+            let motion = &motions[i];
+            moved[i] = match spheres[i].0 {
+                None => Motion::new_position_velocity(
+                    vecmath::vec2_add(motion.position, vecmath::vec2_scale(motion.velocity, dt)),
+                    motion.velocity,
+                ),
+                Some(primary) => {
+                    // the primary has already moved
+                    let mu = G * (bodies[primary].0 + bodies[i].0);
+                    let (position, velocity) = relative_step(
+                        vecmath::vec2_sub(motion.position, motions[primary].position),
+                        vecmath::vec2_sub(motion.velocity, motions[primary].velocity),
+                        mu,
+                        dt,
+                    );
+                    let primary = &moved[primary];
+                    Motion::new(
+                        vecmath::vec2_add(primary.position, position),
+                        vecmath::vec2_add(primary.velocity, velocity),
+                        vecmath::vec2_add(primary.acceleration, gravity(position, mu)),
+                    )
+                }
+            };
+        }
+
+        for (id, motion) in ids.iter().zip(moved) {
+            *world.query_one_mut::<&mut Motion>(*id).unwrap() = motion;
+        }
+    }
+}
+
+/// position and velocity relative to the primary after dt seconds
+fn relative_step(position: Position, velocity: Velocity, mu: f64, dt: f64) -> (Position, Velocity) {
+    let conic = if vecmath::vec2_len(position) > 0.0 {
+        OrbitalElements::from_state(position, velocity, mu).propagate(mu, dt)
+    } else {
+        None
+    };
+    match conic {
+        Some(elements) => elements.state(mu),
+        None => {
+            let velocity =
+                vecmath::vec2_add(velocity, vecmath::vec2_scale(gravity(position, mu), dt));
+            (
+                vecmath::vec2_add(position, vecmath::vec2_scale(velocity, dt)),
+                velocity,
+            )
+        }
+    }
+}
+
+/// acceleration towards the primary. zero at its center
+fn gravity(position: Position, mu: f64) -> Acceleration {
+    let r = vecmath::vec2_len(position);
+    if r > 0.0 {
+        vecmath::vec2_scale(position, -mu / (r * r * r))
+    } else {
+        [0.0, 0.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn long_steps_are_exact() {
+        let mut world = World::new();
+        let star = world.spawn((
+            MassComponent::new(1.0e30),
+            Motion::new_position_velocity([0.0, 0.0], [1.0, 0.0]),
+        ));
+        let mu = G * (1.0e30 + 1.0e20);
+        let speed = f64::sqrt(mu / 1.0e11);
+        let planet = world.spawn((
+            MassComponent::new(1.0e20),
+            Motion::new_position_velocity([1.0e11, 0.0], [1.0, speed]),
+        ));
+        let period = 2.0 * PI * f64::sqrt(1.0e33 / mu);
+
+        let mut conics = PatchedConicsSystem::new();
+        conics.update(&mut world, 1000.0 * period);
+
+        let star = *world.get::<&Motion>(star).unwrap();
+        let planet = *world.get::<&Motion>(planet).unwrap();
+        assert!((star.position[0] - 1000.0 * period).abs() < 1.0e-3);
+        let offset = vecmath::vec2_sub(planet.position, star.position);
+        let error = vecmath::vec2_len(vecmath::vec2_sub(offset, [1.0e11, 0.0]));
+        assert!(error < 1.0, "orbit must close, error {} m", error);
+    }
+
+    #[test]
+    fn parabolic_and_central_bodies_stay_finite() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let mu = G * (1.0e30 + 1.0e10);
+        let escape_speed = f64::sqrt(2.0 * mu / 1.0e11);
+        let parabolic = world.spawn((
+            MassComponent::new(1.0e10),
+            Motion::new_position_velocity([1.0e11, 0.0], [0.0, escape_speed]),
+        ));
+        let central = world.spawn((
+            MassComponent::new(1.0e10),
+            Motion::new_position_velocity([0.0, 0.0], [1.0, 0.0]),
+        ));
+
+        let mut conics = PatchedConicsSystem::new();
+        conics.update(&mut world, 1000.0);
+
+        for body in [parabolic, central] {
+            let motion = world.get::<&Motion>(body).unwrap();
+            assert!(motion.position.iter().all(|x| x.is_finite()));
+            assert!(motion.velocity.iter().all(|v| v.is_finite()));
+        }
+        assert_eq!(
+            world.get::<&Motion>(central).unwrap().position,
+            [1000.0, 0.0]
+        );
+    }
+}
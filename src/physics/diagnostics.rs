@@ -0,0 +1,268 @@
+use crate::physics::gravity::{MassComponent, SofteningComponent, G};
+use crate::physics::motion::{distance_between, Motion};
+use hecs::World;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use vecmath::Vector2;
+
+/// quantities conserved by an isolated system of bodies
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Conserved {
+    pub kinetic: f64,
+    pub potential: f64,
+    pub momentum: Vector2<f64>,
+    pub angular_momentum: f64,
+    /// sum of momentum magnitudes. scale for the momentum drift of systems at rest
+    momentum_scale: f64,
+    /// sum of angular momentum magnitudes
+    angular_momentum_scale: f64,
+    bodies: usize,
+}
+
+impl Conserved {
+    pub fn energy(&self) -> f64 {
+        self.kinetic + self.potential
+    }
+
+    pub fn measure(world: &mut World, softening: f64) -> Self {
+        let mut bodies = vec![];
+        for (_id, (mass, motion, body_softening)) in
+            &mut world.query::<(&MassComponent, &Motion, Option<&SofteningComponent>)>()
+        {
+            let softening = body_softening.map_or(softening, |s| s.length);
+            bodies.push((mass.mass, *motion, softening));
+        }
+
+        let mut conserved = Conserved {
+            bodies: bodies.len(),
+            ..Conserved::default()
+        };
+        for (i, (mass, motion, softening)) in bodies.iter().enumerate() {
+            let momentum = vecmath::vec2_scale(motion.velocity, *mass);
+            let angular_momentum = vecmath::vec2_cross(motion.position, momentum);
+            conserved.kinetic += vecmath::vec2_dot(motion.velocity, momentum) / 2.0;
+            conserved.momentum = vecmath::vec2_add(conserved.momentum, momentum);
+            conserved.momentum_scale += vecmath::vec2_len(momentum);
+            conserved.angular_momentum += angular_momentum;
+            conserved.angular_momentum_scale += angular_momentum.abs();
+
+            for (other_mass, other_motion, other_softening) in bodies[i + 1..].iter() {
+                let distance = distance_between(&motion.position, &other_motion.position);
+                let softening_squared =
+                    (softening * softening + other_softening * other_softening) / 2.0;
+                let softened = f64::sqrt(distance * distance + softening_squared);
+                if softened > 0.0 {
+                    conserved.potential -= G * mass * other_mass / softened;
+                }
+            }
+        }
+        conserved
+    }
+}
+
+/// relative change of conserved quantities since the start
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Drift {
+    pub energy: f64,
+    pub momentum: f64,
+    pub angular_momentum: f64,
+}
+
+impl Drift {
+    fn between(initial: &Conserved, current: &Conserved) -> Self {
+        let relative = |change: f64, scale: f64| if scale > 0.0 { change / scale } else { 0.0 };
+        Drift {
+            energy: relative(
+                (current.energy() - initial.energy()).abs(),
+                initial.energy().abs(),
+            ),
+            momentum: relative(
+                vecmath::vec2_len(vecmath::vec2_sub(current.momentum, initial.momentum)),
+                initial.momentum_scale,
+            ),
+            angular_momentum: relative(
+                (current.angular_momentum - initial.angular_momentum).abs(),
+                initial.angular_momentum_scale,
+            ),
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        self.energy.max(self.momentum).max(self.angular_momentum)
+    }
+}
+
+/// tracks conservation of energy, momentum and angular momentum
+///
+/// measuring potential energy visits every pair of bodies, so the system only runs when
+/// it is enabled, has a drift threshold or writes a log.
+/// quantities are measured once per update, after all physics steps of the frame.
+/// drift is measured from the state of the first update.
+/// collisions change the number of bodies and restart the measurement,
+/// since merges don't conserve energy.
+pub struct DiagnosticsSystem {
+    enabled: bool,
+    softening: f64,
+    threshold: f64,
+    initial: Option<Conserved>,
+    current: Conserved,
+    drift: Drift,
+    log: Option<BufWriter<File>>,
+}
+
+impl Default for DiagnosticsSystem {
+    fn default() -> Self {
+        DiagnosticsSystem::new()
+    }
+}
+
+impl DiagnosticsSystem {
+    pub fn new() -> Self {
+        DiagnosticsSystem {
+            enabled: false,
+            softening: 0.0,
+            threshold: 0.0,
+            initial: None,
+            current: Conserved::default(),
+            drift: Drift::default(),
+            log: None,
+        }
+    }
+
+    /// measure conserved quantities even without a threshold or a log
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// true when updates measure conserved quantities
+    pub fn active(&self) -> bool {
+        self.enabled || self.threshold > 0.0 || self.log.is_some()
+    }
+
+    /// softening length used to calculate potential energy
+    pub fn set_softening(&mut self, softening: f64) {
+        self.softening = softening;
+        self.initial = None;
+    }
+
+    /// relative drift considered too large. zero disables the check
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    /// write diagnostics of every update to a csv file
+    pub fn log_to(&mut self, file: File) {
+        let mut log = BufWriter::new(file);
+        writeln!(
+            log,
+            "time,kinetic,potential,energy,momentum_x,momentum_y,angular_momentum,energy_drift,momentum_drift,angular_momentum_drift"
+        )
+        .expect("can't write diagnostics log");
+        self.log = Some(log);
+    }
+
+    pub fn drift(&self) -> Drift {
+        self.drift
+    }
+
+    /// true when the drift exceeds the threshold
+    pub fn exceeded(&self) -> bool {
+        self.threshold > 0.0 && self.drift.max() > self.threshold
+    }
+
+    pub fn update(&mut self, world: &mut World, time: f64) {
+        if !self.active() {
+            return;
+        }
+        self.current = Conserved::measure(world, self.softening);
+        let initial = match self.initial {
+            Some(initial) if initial.bodies == self.current.bodies => initial,
+            _ => {
+                self.initial = Some(self.current);
+                self.current
+            }
+        };
+        self.drift = Drift::between(&initial, &self.current);
+
+        if let Some(log) = self.log.as_mut() {
+            let c = &self.current;
+            writeln!(
+                log,
+                "{},{},{},{},{},{},{},{},{},{}",
+                time,
+                c.kinetic,
+                c.potential,
+                c.energy(),
+                c.momentum[0],
+                c.momentum[1],
+                c.angular_momentum,
+                self.drift.energy,
+                self.drift.momentum,
+                self.drift.angular_momentum
+            )
+            .expect("can't write diagnostics log");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_two_bodies() {
+        let mut world = World::new();
+        world.spawn((
+            MassComponent::new(2.0),
+            Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),
+        ));
+        world.spawn((
+            MassComponent::new(1.0),
+            Motion::new_position_velocity([-1.0, 0.0], [0.0, -2.0]),
+        ));
+
+        let conserved = Conserved::measure(&mut world, 0.0);
+        assert_eq!(conserved.kinetic, 3.0);
+        assert_eq!(conserved.potential, -G);
+        assert_eq!(conserved.momentum, [0.0, 0.0]);
+        assert_eq!(conserved.angular_momentum, 4.0);
+    }
+
+    #[test]
+    fn drift_is_relative_to_initial_state() {
+        let mut world = World::new();
+        let body = world.spawn((
+            MassComponent::new(2.0),
+            Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),
+        ));
+        let mut diagnostics = DiagnosticsSystem::new();
+        diagnostics.set_threshold(0.1);
+        assert!(diagnostics.active());
+        diagnostics.update(&mut world, 0.0);
+        assert_eq!(diagnostics.drift(), Drift::default());
+
+        world.get::<&mut Motion>(body).unwrap().velocity = [0.0, 1.1];
+        diagnostics.update(&mut world, 1.0);
+        let drift = diagnostics.drift();
+        assert!((drift.energy - 0.21).abs() < 1.0e-12);
+        assert!((drift.momentum - 0.1).abs() < 1.0e-12);
+        assert!(diagnostics.exceeded());
+    }
+
+    #[test]
+    fn disabled_diagnostics_do_not_measure() {
+        let mut world = World::new();
+        world.spawn((
+            MassComponent::new(2.0),
+            Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),
+        ));
+        let mut diagnostics = DiagnosticsSystem::new();
+        assert!(!diagnostics.active());
+        diagnostics.update(&mut world, 0.0);
+        assert_eq!(diagnostics.initial, None);
+
+        diagnostics.set_enabled(true);
+        diagnostics.update(&mut world, 1.0);
+        assert_eq!(diagnostics.current.kinetic, 1.0);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::core::name::name_of;
+use crate::physics::gravity::MassComponent;
+use crate::physics::motion::{distance_between, Motion, Position, Velocity};
+use hecs::{Entity, World};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::mem;
+
+/// close approach of two bodies
+#[derive(Clone, Debug, PartialEq)]
+pub struct Encounter {
+    pub names: (String, String),
+    /// meters
+    pub closest_distance: f64,
+    /// simulated seconds of the closest approach
+    pub closest_time: f64,
+    /// largest relative speed in meters per second
+    pub max_speed: f64,
+}
+
+impl Display for Encounter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} and {} at {:.0} s, closest distance {:.0} m, relative speed up to {:.1} m/s",
+            self.names.0, self.names.1, self.closest_time, self.closest_distance, self.max_speed
+        )
+    }
+}
+
+/// watches for bodies coming closer than the encounter distance
+///
+/// every encounter is reported once, when bodies move apart again,
+/// with the distance and time of the closest approach.
+/// finished encounters are kept until they are taken.
+pub struct EncounterSystem {
+    distance: f64,
+    active: HashMap<(Entity, Entity), Encounter>,
+    finished: Vec<Encounter>,
+}
+
+impl Default for EncounterSystem {
+    fn default() -> Self {
+        EncounterSystem::new()
+    }
+}
+
+impl EncounterSystem {
+    pub fn new() -> Self {
+        EncounterSystem {
+            distance: 0.0,
+            active: HashMap::new(),
+            finished: vec![],
+        }
+    }
+
+    /// encounters finished since the last call
+    pub fn take_finished(&mut self) -> Vec<Encounter> {
+        mem::take(&mut self.finished)
+    }
+
+    /// distance in meters considered a close encounter. zero disables the log
+    pub fn set_distance(&mut self, distance: f64) {
+        self.distance = distance;
+        self.active.clear();
+    }
+
+    pub fn update(&mut self, world: &mut World, time: f64) {
+        if self.distance <= 0.0 {
+            return;
+        }
+        let mut bodies: Vec<(Entity, Position, Velocity)> = vec![];
+        for (id, (_mass, motion)) in &mut world.query::<(&MassComponent, &Motion)>() {
+            bodies.push((id, motion.position, motion.velocity));
+        }
+        bodies.sort_by(|a, b| a.1[0].total_cmp(&b.1[0]));
+
+        let mut close = vec![];
+        for (i, (a, a_position, a_velocity)) in bodies.iter().enumerate() {
+            for (b, b_position, b_velocity) in bodies[i + 1..].iter() {
+                if b_position[0] - a_position[0] > self.distance {
+                    break;
+                }
+                let distance = distance_between(a_position, b_position);
+                if distance < self.distance {
+                    let speed = vecmath::vec2_len(vecmath::vec2_sub(*a_velocity, *b_velocity));
+                    let key = if a < b { (*a, *b) } else { (*b, *a) };
+                    close.push((key, distance, speed));
+                }
+            }
+        }
+
+        for (key, distance, speed) in close.iter() {
+            let encounter = self.active.entry(*key).or_insert_with(|| Encounter {
+                names: (name_of(world, key.0), name_of(world, key.1)),
+                closest_distance: f64::MAX,
+                closest_time: time,
+                max_speed: 0.0,
+            });
+            if *distance < encounter.closest_distance {
+                encounter.closest_distance = *distance;
+                encounter.closest_time = time;
+            }
+            encounter.max_speed = encounter.max_speed.max(*speed);
+        }
+
+        let finished: Vec<(Entity, Entity)> = self
+            .active
+            .keys()
+            .filter(|key| !close.iter().any(|(k, _, _)| k == *key))
+            .copied()
+            .collect();
+        for key in finished {
+            let encounter = self.active.remove(&key).unwrap();
+            self.finished.push(encounter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::name::NameComponent;
+
+    #[test]
+    fn encounter_is_reported_once_bodies_move_apart() {
+        let mut world = World::new();
+        let spawn = |world: &mut World, name: &str, position: Position, velocity: Velocity| {
+            world.spawn((
+                NameComponent::new(String::from(name)),
+                MassComponent::new(1.0),
+                Motion::new_position_velocity(position, velocity),
+            ))
+        };
+        spawn(&mut world, "Ship", [0.0, 0.0], [0.0, 0.0]);
+        let comet = spawn(&mut world, "Comet", [-30.0, 5.0], [10.0, 0.0]);
+        let mut encounters = EncounterSystem::new();
+        encounters.set_distance(20.0);
+
+        for step in 0..7 {
+            encounters.update(&mut world, step as f64);
+            let mut motion = world.get::<&mut Motion>(comet).unwrap();
+            motion.position = vecmath::vec2_add(motion.position, motion.velocity);
+        }
+        let finished = encounters.take_finished();
+        assert_eq!(finished.len(), 1);
+        let encounter = &finished[0];
+        assert_eq!(encounter.closest_distance, 5.0);
+        assert_eq!(encounter.closest_time, 3.0);
+        assert_eq!(encounter.max_speed, 10.0);
+        assert_eq!(
+            encounter.to_string(),
+            "Ship and Comet at 3 s, closest distance 5 m, relative speed up to 10.0 m/s"
+        );
+        assert!(encounters.take_finished().is_empty());
+    }
+}
@@ -1,12 +1,27 @@
+use crate::physics::barnes_hut::QuadTree;
 use crate::physics::force::{Force, ForceComponent};
-use crate::physics::motion::{distance_between, Acceleration, Motion, Position};
+use crate::physics::motion::{distance_between, Motion, Position, Velocity};
+use crate::physics::parallel;
 use hecs::{Entity, World};
-use std::collections::HashMap;
+use serde::Deserialize;
 use vecmath;
 
-const G: f64 = 6.674e-11;
+pub const G: f64 = 6.674e-11;
 pub type Mass = f64;
 
+/// plummer softening length of the body in meters
+///
+/// overrides the global softening of the gravity system.
+pub struct SofteningComponent {
+    pub length: f64,
+}
+
+impl SofteningComponent {
+    pub fn new(length: f64) -> Self {
+        SofteningComponent { length }
+    }
+}
+
 pub struct MassComponent {
     pub mass: Mass,
 }
@@ -17,41 +32,121 @@ impl MassComponent {
     }
 }
 
-struct GravityCalculation {
+/// position and velocity of the center of mass of all bodies. none without massive bodies
+pub fn center_of_mass(world: &World) -> Option<(Position, Velocity)> {
+    let mut total = 0.0;
+    let mut position = [0.0, 0.0];
+    let mut velocity = [0.0, 0.0];
+    for (_id, (mass, motion)) in &mut world.query::<(&MassComponent, &Motion)>() {
+        total += mass.mass;
+        position = vecmath::vec2_add(position, vecmath::vec2_scale(motion.position, mass.mass));
+        velocity = vecmath::vec2_add(velocity, vecmath::vec2_scale(motion.velocity, mass.mass));
+    }
+    if total <= 0.0 {
+        return None;
+    }
+    Some((
+        vecmath::vec2_scale(position, 1.0 / total),
+        vecmath::vec2_scale(velocity, 1.0 / total),
+    ))
+}
+
+/// shift every body so the center of mass rests in the origin
+pub fn center_on_barycenter(world: &mut World) {
+    let (position, velocity) = match center_of_mass(world) {
+        Some(center) => center,
+        None => return,
+    };
+    for (_id, motion) in world.query_mut::<&mut Motion>() {
+        motion.position = vecmath::vec2_sub(motion.position, position);
+        motion.velocity = vecmath::vec2_sub(motion.velocity, velocity);
+    }
+}
+
+/// algorithm used to calculate gravitational forces between bodies
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum GravitySolver {
+    /// exact sum over every pair of bodies. O(n^2)
+    #[default]
+    Direct,
+    /// barnes-hut quadtree approximation. O(n log n)
+    ///
+    /// groups of bodies that look smaller than opening_angle (size / distance)
+    /// are replaced by their center of mass. zero opening angle gives exact forces.
+    BarnesHut { opening_angle: f64 },
+    /// exact sum split between threads. every pair is calculated once
+    ///
+    /// results are the same for any number of threads. zero uses all cores.
+    ParallelDirect { threads: usize },
+}
+
+pub struct GravityCalculation {
     pub mass: Mass,
     pub position: Position,
+    pub softening: f64,
     entity: Entity,
 }
 
 impl GravityCalculation {
     pub fn acceleration(&self, rhs: &GravityCalculation) -> Force {
-        let distance = distance_between(&self.position, &rhs.position);
-        if distance == 0.0 {
-            return [0.0, 0.0];
-        }
-        let distance_squared = distance * distance;
-        let mass_product = self.mass * rhs.mass;
-        let force = G * mass_product / distance_squared;
-        let direction = vecmath::vec2_normalized(vecmath::vec2_sub(rhs.position, self.position));
-        vecmath::vec2_scale(direction, force)
+        let softening_squared =
+            (self.softening * self.softening + rhs.softening * rhs.softening) / 2.0;
+        attraction(
+            self.mass,
+            self.position,
+            rhs.mass,
+            rhs.position,
+            softening_squared,
+        )
     }
 }
 
-fn accelerations(bodies: &[GravityCalculation]) -> HashMap<Entity, Acceleration> {
-    let mut matrix = HashMap::new();
-
-    for x in bodies.iter() {
-        let mut acceleration: Acceleration = [0.0, 0.0];
-        for y in bodies.iter() {
-            acceleration = vecmath::vec2_add(acceleration, x.acceleration(y));
-        }
-        matrix.insert(x.entity, acceleration);
+/// force pulling the body of mass towards the attractor
+///
+/// plummer softening replaces the squared distance with distance^2 + softening^2,
+/// so the force stays finite and vanishes when bodies come together.
+pub fn attraction(
+    mass: Mass,
+    position: Position,
+    attractor_mass: Mass,
+    attractor_position: Position,
+    softening_squared: f64,
+) -> Force {
+    let distance = distance_between(&position, &attractor_position);
+    if distance == 0.0 {
+        return [0.0, 0.0];
     }
+    let softened_squared = distance * distance + softening_squared;
+    let mass_product = mass * attractor_mass;
+    let force = G * mass_product * distance / (softened_squared * softened_squared.sqrt());
+    let direction = vecmath::vec2_normalized(vecmath::vec2_sub(attractor_position, position));
+    vecmath::vec2_scale(direction, force)
+}
 
-    matrix
+fn direct_forces(bodies: &[GravityCalculation]) -> Vec<Force> {
+    bodies
+        .iter()
+        .map(|x| {
+            let mut force: Force = [0.0, 0.0];
+            for y in bodies.iter() {
+                force = vecmath::vec2_add(force, x.acceleration(y));
+            }
+            force
+        })
+        .collect()
 }
 
-pub struct GravitySystem {}
+fn barnes_hut_forces(bodies: &[GravityCalculation], opening_angle: f64) -> Vec<Force> {
+    let tree = QuadTree::build(bodies);
+    (0..bodies.len())
+        .map(|i| tree.force(bodies, i, opening_angle))
+        .collect()
+}
+
+pub struct GravitySystem {
+    solver: GravitySolver,
+    softening: f64,
+}
 
 impl Default for GravitySystem {
     fn default() -> Self {
@@ -61,23 +156,159 @@ impl Default for GravitySystem {
 
 impl GravitySystem {
     pub fn new() -> Self {
-        GravitySystem {}
+        GravitySystem {
+            solver: GravitySolver::default(),
+            softening: 0.0,
+        }
+    }
+
+    pub fn set_solver(&mut self, solver: GravitySolver) {
+        self.solver = solver;
+    }
+
+    /// softening length of bodies without their own softening
+    pub fn set_softening(&mut self, softening: f64) {
+        self.softening = softening;
     }
 
     pub fn update(&mut self, world: &mut World) {
         let mut gravities: Vec<GravityCalculation> = vec![];
-        for (id, (g, m)) in &mut world.query::<(&MassComponent, &Motion)>() {
+        for (id, (g, m, s)) in
+            &mut world.query::<(&MassComponent, &Motion, Option<&SofteningComponent>)>()
+        {
             gravities.push(GravityCalculation {
                 position: m.position,
                 mass: g.mass,
+                softening: s.map_or(self.softening, |s| s.length),
                 entity: id,
             });
         }
-        let acceleration_values = accelerations(&gravities);
-        for (id, (force_component,)) in &mut world.query::<(&mut ForceComponent,)>() {
-            if let Some(accel) = acceleration_values.get(&id) {
-                force_component.force = *accel;
+        let forces = match self.solver {
+            GravitySolver::Direct => direct_forces(&gravities),
+            GravitySolver::BarnesHut { opening_angle } => {
+                barnes_hut_forces(&gravities, opening_angle)
+            }
+            GravitySolver::ParallelDirect { threads } => parallel::forces(&gravities, threads),
+        };
+        for (body, force) in gravities.iter().zip(forces) {
+            if let Ok(force_component) = world.query_one_mut::<&mut ForceComponent>(body.entity) {
+                force_component.force = force;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centered_on_barycenter() {
+        let mut world = World::new();
+        world.spawn((
+            MassComponent::new(3.0),
+            Motion::new_position_velocity([1.0, 2.0], [0.0, 1.0]),
+        ));
+        world.spawn((
+            MassComponent::new(1.0),
+            Motion::new_position_velocity([5.0, 2.0], [4.0, -3.0]),
+        ));
+        center_on_barycenter(&mut world);
+        assert_eq!(center_of_mass(&world), Some(([0.0, 0.0], [0.0, 0.0])));
+    }
+
+    /// bodies scattered over a disk by a simple deterministic generator
+    fn scattered_bodies(count: usize) -> Vec<GravityCalculation> {
+        let mut world = World::new();
+        let mut seed: u64 = 42;
+        let mut random = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..count)
+            .map(|_| {
+                let angle = random() * std::f64::consts::TAU;
+                let radius = random() * 1.0e9;
+                GravityCalculation {
+                    mass: 1.0e20 + random() * 1.0e22,
+                    position: [radius * angle.cos(), radius * angle.sin()],
+                    softening: 0.0,
+                    entity: world.spawn(()),
+                }
+            })
+            .collect()
+    }
+
+    /// largest force error relative to the mean force magnitude
+    fn max_relative_error(expected: &[Force], actual: &[Force]) -> f64 {
+        let mean =
+            expected.iter().map(|f| vecmath::vec2_len(*f)).sum::<f64>() / expected.len() as f64;
+        expected
+            .iter()
+            .zip(actual)
+            .map(|(e, a)| vecmath::vec2_len(vecmath::vec2_sub(*e, *a)) / mean)
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn barnes_hut_without_opening_angle_is_exact() {
+        let bodies = scattered_bodies(200);
+        let direct = direct_forces(&bodies);
+        let tree = barnes_hut_forces(&bodies, 0.0);
+        assert!(max_relative_error(&direct, &tree) < 1.0e-9);
+    }
+
+    #[test]
+    fn barnes_hut_approximates_direct_sum() {
+        let bodies = scattered_bodies(500);
+        let direct = direct_forces(&bodies);
+        let tree = barnes_hut_forces(&bodies, 0.5);
+        let error = max_relative_error(&direct, &tree);
+        assert!(error < 0.05, "error {}", error);
+    }
+
+    #[test]
+    fn parallel_solver_matches_direct_sum() {
+        let bodies = scattered_bodies(300);
+        let direct = direct_forces(&bodies);
+        let parallel = parallel::forces(&bodies, 4);
+        assert!(max_relative_error(&direct, &parallel) < 1.0e-9);
+    }
+
+    #[test]
+    fn parallel_solver_does_not_depend_on_threads() {
+        let bodies = scattered_bodies(600);
+        let single = parallel::forces(&bodies, 1);
+        for threads in [2, 3, 7, 16] {
+            assert_eq!(single, parallel::forces(&bodies, threads));
+        }
+    }
+
+    #[test]
+    fn softening_keeps_close_forces_finite() {
+        let mut bodies = scattered_bodies(2);
+        bodies[0].position = [0.0, 0.0];
+        bodies[0].softening = 1.0e3;
+        bodies[1].softening = 1.0e3;
+
+        bodies[1].position = [1.0, 0.0];
+        let softened = vecmath::vec2_len(bodies[0].acceleration(&bodies[1]));
+        let limit = G * bodies[0].mass * bodies[1].mass / (1.0e3 * 1.0e3);
+        assert!(softened < limit * 1.0e-3);
+
+        bodies[1].position = [1.0e9, 0.0];
+        let far = bodies[0].acceleration(&bodies[1]);
+        bodies[0].softening = 0.0;
+        bodies[1].softening = 0.0;
+        let exact = bodies[0].acceleration(&bodies[1]);
+        assert!((far[0] - exact[0]).abs() < exact[0] * 1.0e-9);
+    }
+
+    #[test]
+    fn barnes_hut_handles_coincident_bodies() {
+        let mut bodies = scattered_bodies(2);
+        bodies[1].position = bodies[0].position;
+        let forces = barnes_hut_forces(&bodies, 0.5);
+        assert_eq!(forces, vec![[0.0, 0.0], [0.0, 0.0]]);
+    }
+}
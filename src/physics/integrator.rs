@@ -0,0 +1,416 @@
+use crate::physics::motion::{Acceleration, Motion, Position, Velocity};
+use hecs::{Entity, World};
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// evaluates accelerations of all bodies for their current positions
+///
+/// multi-stage integrators call it several times per step,
+/// moving bodies to intermediate positions in between.
+pub trait AccelerationField {
+    fn evaluate(&mut self, world: &mut World);
+}
+
+/// numerical scheme used to advance bodies in time
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum Integrator {
+    /// first order semi-implicit euler. cheap, but drifts on long runs
+    #[default]
+    SemiImplicitEuler,
+    /// second order symplectic kick-drift-kick leapfrog
+    VelocityVerlet,
+    /// fourth order symplectic integrator composed of three leapfrog steps
+    Yoshida4,
+    /// classic fourth order runge-kutta. accurate, but not symplectic
+    RungeKutta4,
+    /// adaptive dormand-prince 5(4) that keeps the local relative error under tolerance
+    DormandPrince { tolerance: f64 },
+}
+
+/// relative error tolerance of the adaptive integrator selected from cli
+pub const DEFAULT_TOLERANCE: f64 = 1.0e-9;
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "euler" => Ok(Integrator::SemiImplicitEuler),
+            "verlet" | "leapfrog" => Ok(Integrator::VelocityVerlet),
+            "yoshida4" => Ok(Integrator::Yoshida4),
+            "rk4" => Ok(Integrator::RungeKutta4),
+            "dopri" => Ok(Integrator::DormandPrince {
+                tolerance: DEFAULT_TOLERANCE,
+            }),
+            _ => Err(format!(
+                "unknown integrator {}. use one of: euler, verlet, yoshida4, rk4, dopri",
+                s
+            )),
+        }
+    }
+}
+
+impl Integrator {
+    /// advance all moving bodies by dt seconds
+    ///
+    /// adaptive integrators cover dt with as many steps as their tolerance requires.
+    pub fn step(&self, world: &mut World, dt: f64, field: &mut impl AccelerationField) {
+        match self {
+            Integrator::SemiImplicitEuler => semi_implicit_euler(world, dt, field),
+            Integrator::VelocityVerlet => velocity_verlet(world, dt, false, field),
+            Integrator::Yoshida4 => yoshida4(world, dt, field),
+            Integrator::RungeKutta4 => runge_kutta4(world, dt, field),
+            Integrator::DormandPrince { tolerance } => {
+                let mut step_size = dt;
+                adaptive_dormand_prince(world, dt, *tolerance, &mut step_size, field);
+            }
+        }
+    }
+}
+
+/// move bodies with their current velocities
+fn drift(world: &mut World, dt: f64) {
+    for (_id, motion) in world.query_mut::<&mut Motion>() {
+        let dx = vecmath::vec2_scale(motion.velocity, dt);
+        motion.position = vecmath::vec2_add(motion.position, dx);
+    }
+}
+
+/// change velocities of bodies with their current accelerations
+fn kick(world: &mut World, dt: f64) {
+    for (_id, motion) in world.query_mut::<&mut Motion>() {
+        let dv = vecmath::vec2_scale(motion.acceleration, dt);
+        motion.velocity = vecmath::vec2_add(motion.velocity, dv);
+    }
+}
+
+fn semi_implicit_euler(world: &mut World, dt: f64, field: &mut impl AccelerationField) {
+    field.evaluate(world);
+    kick(world, dt);
+    drift(world, dt);
+}
+
+/// kick-drift-kick step. current accelerations left by the previous step are reused
+pub fn velocity_verlet(
+    world: &mut World,
+    dt: f64,
+    accelerations_current: bool,
+    field: &mut impl AccelerationField,
+) {
+    if !accelerations_current {
+        field.evaluate(world);
+    }
+    kick(world, dt / 2.0);
+    drift(world, dt);
+    field.evaluate(world);
+    kick(world, dt / 2.0);
+}
+
+fn yoshida4(world: &mut World, dt: f64, field: &mut impl AccelerationField) {
+    let cbrt2 = 2.0_f64.cbrt();
+    let w1 = 1.0 / (2.0 - cbrt2);
+    let w0 = -cbrt2 * w1;
+    let drifts = [w1 / 2.0, (w0 + w1) / 2.0, (w0 + w1) / 2.0, w1 / 2.0];
+    let kicks = [w1, w0, w1];
+
+    for (i, k) in kicks.iter().enumerate() {
+        drift(world, drifts[i] * dt);
+        field.evaluate(world);
+        kick(world, k * dt);
+    }
+    drift(world, drifts[3] * dt);
+}
+
+pub type State = (Entity, Position, Velocity);
+type Derivative = (Velocity, Acceleration);
+
+/// positions and velocities of all moving bodies
+pub fn snapshot(world: &mut World) -> Vec<State> {
+    world
+        .query_mut::<&Motion>()
+        .into_iter()
+        .map(|(id, motion)| (id, motion.position, motion.velocity))
+        .collect()
+}
+
+/// put bodies back to the snapshot state
+pub fn restore(world: &mut World, states: &[State]) {
+    for (id, position, velocity) in states {
+        let motion = world.query_one_mut::<&mut Motion>(*id).unwrap();
+        motion.position = *position;
+        motion.velocity = *velocity;
+    }
+}
+
+fn runge_kutta4(world: &mut World, dt: f64, field: &mut impl AccelerationField) {
+    let initial = snapshot(world);
+
+    field.evaluate(world);
+    let k1 = derivatives(world, &initial);
+    move_to_stage(world, &initial, &k1, dt / 2.0);
+    field.evaluate(world);
+    let k2 = derivatives(world, &initial);
+    move_to_stage(world, &initial, &k2, dt / 2.0);
+    field.evaluate(world);
+    let k3 = derivatives(world, &initial);
+    move_to_stage(world, &initial, &k3, dt);
+    field.evaluate(world);
+    let k4 = derivatives(world, &initial);
+
+    for (i, (id, position, velocity)) in initial.iter().enumerate() {
+        let motion = world.query_one_mut::<&mut Motion>(*id).unwrap();
+        let dx = weighted_sum([k1[i].0, k2[i].0, k3[i].0, k4[i].0]);
+        let dv = weighted_sum([k1[i].1, k2[i].1, k3[i].1, k4[i].1]);
+        motion.position = vecmath::vec2_add(*position, vecmath::vec2_scale(dx, dt / 6.0));
+        motion.velocity = vecmath::vec2_add(*velocity, vecmath::vec2_scale(dv, dt / 6.0));
+    }
+}
+
+/// velocities and accelerations of bodies at the current stage
+fn derivatives(world: &mut World, states: &[State]) -> Vec<Derivative> {
+    states
+        .iter()
+        .map(|(id, _, _)| {
+            let motion = world.query_one_mut::<&Motion>(*id).unwrap();
+            (motion.velocity, motion.acceleration)
+        })
+        .collect()
+}
+
+/// put bodies to the initial state advanced by h along the derivative
+fn move_to_stage(world: &mut World, initial: &[State], derivatives: &[Derivative], h: f64) {
+    for ((id, position, velocity), (dx, dv)) in initial.iter().zip(derivatives) {
+        let motion = world.query_one_mut::<&mut Motion>(*id).unwrap();
+        motion.position = vecmath::vec2_add(*position, vecmath::vec2_scale(*dx, h));
+        motion.velocity = vecmath::vec2_add(*velocity, vecmath::vec2_scale(*dv, h));
+    }
+}
+
+/// linear combination of stage derivatives for every body
+fn combine(stages: &[Vec<Derivative>], weights: &[f64]) -> Vec<Derivative> {
+    let bodies = stages.first().map_or(0, |stage| stage.len());
+    (0..bodies)
+        .map(|i| {
+            stages
+                .iter()
+                .zip(weights)
+                .fold(([0.0, 0.0], [0.0, 0.0]), |(dx, dv), (stage, w)| {
+                    (
+                        vecmath::vec2_add(dx, vecmath::vec2_scale(stage[i].0, *w)),
+                        vecmath::vec2_add(dv, vecmath::vec2_scale(stage[i].1, *w)),
+                    )
+                })
+        })
+        .collect()
+}
+
+const DP_STAGES: [&[f64]; 6] = [
+    &[1.0 / 5.0],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+    ],
+    &[
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+    ],
+    // fifth order solution. its derivative is used by the error estimate
+    &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+
+/// difference between the fifth and the embedded fourth order solutions
+const DP_ERROR: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+/// upper bound of adaptive steps covering a single interval
+const MAX_ADAPTIVE_STEPS: usize = 10_000;
+
+/// cover dt with as many dormand-prince steps as the error tolerance requires
+///
+/// step_size is the size of the first step to try. it is left at the size to try next.
+/// steps never get shorter than dt / MAX_ADAPTIVE_STEPS to keep frame time bounded,
+/// even if that means exceeding the tolerance.
+/// returns the number of accepted steps
+pub fn adaptive_dormand_prince(
+    world: &mut World,
+    dt: f64,
+    tolerance: f64,
+    step_size: &mut f64,
+    field: &mut impl AccelerationField,
+) -> usize {
+    let min_step = dt / MAX_ADAPTIVE_STEPS as f64;
+    if *step_size <= 0.0 {
+        *step_size = dt;
+    }
+    let mut elapsed = 0.0;
+    let mut steps = 0;
+    while dt - elapsed > min_step * 1.0e-6 {
+        let h = step_size.max(min_step).min(dt - elapsed);
+        let initial = snapshot(world);
+        let error = dormand_prince(world, h, tolerance, field);
+        let next_step = h * step_factor(error);
+        if error <= 1.0 || h <= min_step {
+            elapsed += h;
+            steps += 1;
+            // a step shortened to hit the end of the interval says little about the next one
+            *step_size = if h < *step_size {
+                step_size.max(next_step)
+            } else {
+                next_step
+            };
+        } else {
+            restore(world, &initial);
+            *step_size = next_step;
+        }
+    }
+    steps
+}
+
+/// how much to change the step for the given relative error of a fifth order method
+fn step_factor(error: f64) -> f64 {
+    if error == 0.0 {
+        return 5.0;
+    }
+    (0.9 * error.powf(-0.2)).clamp(0.2, 5.0)
+}
+
+/// make a single dormand-prince step and leave bodies at the fifth order solution
+///
+/// returns the estimated local error relative to tolerance, so values above one
+/// mean the step should be rejected and retried with a smaller dt.
+/// errors of positions and velocities are measured against tolerance * (1 + max(|y|, |y_new|)),
+/// so the tolerance is absolute for values below one and relative above.
+pub fn dormand_prince(
+    world: &mut World,
+    dt: f64,
+    tolerance: f64,
+    field: &mut impl AccelerationField,
+) -> f64 {
+    let initial = snapshot(world);
+    field.evaluate(world);
+    let mut stages = vec![derivatives(world, &initial)];
+    for weights in DP_STAGES.iter() {
+        move_to_stage(world, &initial, &combine(&stages, weights), dt);
+        field.evaluate(world);
+        stages.push(derivatives(world, &initial));
+    }
+
+    let errors = combine(&stages, &DP_ERROR);
+    let mut error_ratio: f64 = 0.0;
+    let scale = |before: vecmath::Vector2<f64>, after: vecmath::Vector2<f64>| {
+        tolerance * (1.0 + vecmath::vec2_len(before).max(vecmath::vec2_len(after)))
+    };
+    for ((id, position, velocity), (dx_error, dv_error)) in initial.iter().zip(errors) {
+        let motion = world.query_one_mut::<&Motion>(*id).unwrap();
+        let position_error = vecmath::vec2_len(dx_error) * dt;
+        let velocity_error = vecmath::vec2_len(dv_error) * dt;
+        error_ratio = error_ratio
+            .max(position_error / scale(*position, motion.position))
+            .max(velocity_error / scale(*velocity, motion.velocity));
+    }
+    error_ratio
+}
+
+/// runge-kutta weighted sum of stage derivatives: k1 + 2k2 + 2k3 + k4
+fn weighted_sum(k: [vecmath::Vector2<f64>; 4]) -> vecmath::Vector2<f64> {
+    let middle = vecmath::vec2_scale(vecmath::vec2_add(k[1], k[2]), 2.0);
+    vecmath::vec2_add(vecmath::vec2_add(k[0], middle), k[3])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// unit attractor fixed at the origin
+    pub(crate) struct CentralField {}
+
+    impl AccelerationField for CentralField {
+        fn evaluate(&mut self, world: &mut World) {
+            for (_id, motion) in world.query_mut::<&mut Motion>() {
+                let r = vecmath::vec2_len(motion.position);
+                motion.acceleration = vecmath::vec2_scale(motion.position, -1.0 / (r * r * r));
+            }
+        }
+    }
+
+    /// position error after a single revolution on a unit circular orbit
+    fn orbit_error(integrator: Integrator, steps: usize) -> f64 {
+        let mut world = World::new();
+        let body = world.spawn((Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),));
+        let dt = 2.0 * PI / steps as f64;
+        for _ in 0..steps {
+            integrator.step(&mut world, dt, &mut CentralField {});
+        }
+        let motion = world.get::<&Motion>(body).unwrap();
+        vecmath::vec2_len(vecmath::vec2_sub(motion.position, [1.0, 0.0]))
+    }
+
+    #[test]
+    fn integrators_close_circular_orbit() {
+        assert!(orbit_error(Integrator::SemiImplicitEuler, 1000) < 5e-2);
+        assert!(orbit_error(Integrator::VelocityVerlet, 1000) < 1e-4);
+        assert!(orbit_error(Integrator::Yoshida4, 1000) < 1e-8);
+        assert!(orbit_error(Integrator::RungeKutta4, 1000) < 1e-8);
+        let dopri = Integrator::DormandPrince { tolerance: 1e-9 };
+        assert!(orbit_error(dopri, 1000) < 1e-8);
+    }
+
+    #[test]
+    fn dormand_prince_controls_error_of_long_steps() {
+        let dopri = Integrator::DormandPrince { tolerance: 1e-9 };
+        assert!(orbit_error(dopri, 4) < 1e-6);
+    }
+
+    #[test]
+    fn dormand_prince_error_does_not_depend_on_step() {
+        let error = |dt: f64| {
+            let mut world = World::new();
+            world.spawn((Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),));
+            dormand_prince(&mut world, dt, 1.0, &mut CentralField {})
+        };
+        // local error of a fifth order method shrinks as dt^5 when the norm is the state scale
+        let ratio = error(0.1) / error(0.05);
+        assert!((ratio.log2() - 5.0).abs() < 0.5, "ratio {}", ratio);
+    }
+
+    #[test]
+    fn higher_order_integrators_are_more_accurate() {
+        let euler = orbit_error(Integrator::SemiImplicitEuler, 200);
+        let verlet = orbit_error(Integrator::VelocityVerlet, 200);
+        let yoshida = orbit_error(Integrator::Yoshida4, 200);
+        assert!(verlet < euler);
+        assert!(yoshida < verlet);
+    }
+
+    #[test]
+    fn parse_integrator() {
+        assert_eq!(
+            Integrator::from_str("leapfrog"),
+            Ok(Integrator::VelocityVerlet)
+        );
+        assert_eq!(Integrator::from_str("rk4"), Ok(Integrator::RungeKutta4));
+        assert!(Integrator::from_str("midpoint").is_err());
+    }
+}
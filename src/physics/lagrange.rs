@@ -0,0 +1,191 @@
+use crate::physics::gravity::{Mass, MassComponent};
+use crate::physics::motion::{Motion, Position, Velocity};
+use hecs::{Entity, World};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+
+/// iterations of bisection searching for collinear points
+const BISECTION_STEPS: usize = 100;
+
+/// equilibrium point of the circular restricted three body problem
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub enum LagrangePoint {
+    /// between the primary and the secondary
+    L1,
+    /// behind the secondary
+    L2,
+    /// behind the primary, opposite to the secondary
+    L3,
+    /// sixty degrees ahead of the secondary on its orbit
+    L4,
+    /// sixty degrees behind the secondary on its orbit
+    L5,
+}
+
+impl LagrangePoint {
+    pub const ALL: [LagrangePoint; 5] = [
+        LagrangePoint::L1,
+        LagrangePoint::L2,
+        LagrangePoint::L3,
+        LagrangePoint::L4,
+        LagrangePoint::L5,
+    ];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Display for LagrangePoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// position and velocity of lagrange points of the pair of bodies, indexed by the point
+///
+/// the pair is assumed to be on a circular orbit, points rotate with it around its barycenter.
+/// none when bodies are at the same place or have no mass.
+pub fn lagrange_points(
+    (primary_mass, primary): (Mass, &Motion),
+    (secondary_mass, secondary): (Mass, &Motion),
+) -> Option<[(Position, Velocity); 5]> {
+    let total = primary_mass + secondary_mass;
+    let axis = vecmath::vec2_sub(secondary.position, primary.position);
+    let distance = vecmath::vec2_len(axis);
+    if total <= 0.0 || distance <= 0.0 {
+        return None;
+    }
+    let mu = secondary_mass / total;
+    let along = vecmath::vec2_scale(axis, 1.0 / distance);
+    let across = [-along[1], along[0]];
+    let relative_velocity = vecmath::vec2_sub(secondary.velocity, primary.velocity);
+    let angular_momentum = vecmath::vec2_cross(axis, relative_velocity);
+    let angular_velocity = angular_momentum / (distance * distance);
+    // l4 leads the secondary, on the left of the axis for counter-clockwise orbits
+    let ahead = if angular_momentum < 0.0 { -1.0 } else { 1.0 };
+    let barycenter = vecmath::vec2_add(primary.position, vecmath::vec2_scale(axis, mu));
+    let barycenter_velocity = vecmath::vec2_scale(
+        vecmath::vec2_add(
+            vecmath::vec2_scale(primary.velocity, primary_mass),
+            vecmath::vec2_scale(secondary.velocity, secondary_mass),
+        ),
+        1.0 / total,
+    );
+
+    // coordinates in units of the distance with the barycenter in the origin
+    let height = ahead * 3.0_f64.sqrt() / 2.0;
+    let local = [
+        [collinear_point(mu, -mu, 1.0 - mu), 0.0],
+        [collinear_point(mu, 1.0 - mu, 2.0), 0.0],
+        [collinear_point(mu, -2.0, -mu), 0.0],
+        [0.5 - mu, height],
+        [0.5 - mu, -height],
+    ];
+    Some(local.map(|[x, y]| {
+        let offset = vecmath::vec2_add(
+            vecmath::vec2_scale(along, x * distance),
+            vecmath::vec2_scale(across, y * distance),
+        );
+        let rotation = [-angular_velocity * offset[1], angular_velocity * offset[0]];
+        (
+            vecmath::vec2_add(barycenter, offset),
+            vecmath::vec2_add(barycenter_velocity, rotation),
+        )
+    }))
+}
+
+/// lagrange points of the pair of entities at their current motion
+pub fn pair_lagrange_points(
+    world: &World,
+    primary: Entity,
+    secondary: Entity,
+) -> Option<[(Position, Velocity); 5]> {
+    let body = |entity: Entity| {
+        let mass = world.get::<&MassComponent>(entity).ok()?.mass;
+        let motion = *world.get::<&Motion>(entity).ok()?;
+        Some((mass, motion))
+    };
+    let (primary_mass, primary) = body(primary)?;
+    let (secondary_mass, secondary) = body(secondary)?;
+    lagrange_points((primary_mass, &primary), (secondary_mass, &secondary))
+}
+
+/// point on the axis between lower and upper bounds where gravity of both bodies is balanced
+/// by the centrifugal force of the rotating frame
+///
+/// the primary is at -mu and the secondary at 1 - mu.
+fn collinear_point(mu: f64, lower: f64, upper: f64) -> f64 {
+    let force = |x: f64| {
+        let to_primary = x + mu;
+        let to_secondary = x - 1.0 + mu;
+        x - (1.0 - mu) * to_primary / to_primary.abs().powi(3)
+            - mu * to_secondary / to_secondary.abs().powi(3)
+    };
+    let (mut lower, mut upper) = (lower, upper);
+    for _ in 0..BISECTION_STEPS {
+        let middle = (lower + upper) / 2.0;
+        if force(middle) < 0.0 {
+            lower = middle;
+        } else {
+            upper = middle;
+        }
+    }
+    (lower + upper) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::gravity::G;
+
+    /// pair on a circular orbit around the origin with the secondary on the x axis
+    fn circular_pair(clockwise: bool) -> ((Mass, Motion), (Mass, Motion), f64) {
+        let (primary_mass, secondary_mass, distance): (Mass, Mass, f64) = (1.0e24, 1.0e22, 1.0e8);
+        let total = primary_mass + secondary_mass;
+        let mu = secondary_mass / total;
+        let direction = if clockwise { -1.0 } else { 1.0 };
+        let omega = direction * f64::sqrt(G * total / distance.powi(3));
+        let primary =
+            Motion::new_position_velocity([-mu * distance, 0.0], [0.0, -omega * mu * distance]);
+        let secondary = Motion::new_position_velocity(
+            [(1.0 - mu) * distance, 0.0],
+            [0.0, omega * (1.0 - mu) * distance],
+        );
+        ((primary_mass, primary), (secondary_mass, secondary), omega)
+    }
+
+    #[test]
+    fn points_are_at_rest_in_the_rotating_frame() {
+        let ((m1, primary), (m2, secondary), omega) = circular_pair(false);
+        let points = lagrange_points((m1, &primary), (m2, &secondary)).unwrap();
+        for (position, velocity) in points {
+            let gravity = |mass: Mass, body: &Motion| {
+                let offset = vecmath::vec2_sub(body.position, position);
+                let r = vecmath::vec2_len(offset);
+                vecmath::vec2_scale(offset, G * mass / r.powi(3))
+            };
+            let acceleration = vecmath::vec2_add(gravity(m1, &primary), gravity(m2, &secondary));
+            let centripetal = vecmath::vec2_scale(position, -omega * omega);
+            let error = vecmath::vec2_len(vecmath::vec2_sub(acceleration, centripetal));
+            assert!(error < 1.0e-9 * vecmath::vec2_len(centripetal));
+
+            let expected = [-omega * position[1], omega * position[0]];
+            assert!(vecmath::vec2_len(vecmath::vec2_sub(velocity, expected)) < 1.0e-6);
+        }
+    }
+
+    #[test]
+    fn l4_leads_the_secondary() {
+        for clockwise in [false, true] {
+            let ((m1, primary), (m2, secondary), _) = circular_pair(clockwise);
+            let points = lagrange_points((m1, &primary), (m2, &secondary)).unwrap();
+            let (l4, _) = points[LagrangePoint::L4.index()];
+            let leads = vecmath::vec2_dot(l4, secondary.velocity) > 0.0;
+            assert!(leads, "l4 trails the secondary, clockwise: {}", clockwise);
+            let to_primary = vecmath::vec2_len(vecmath::vec2_sub(l4, primary.position));
+            let to_secondary = vecmath::vec2_len(vecmath::vec2_sub(l4, secondary.position));
+            assert!((to_primary - to_secondary).abs() < 1.0e-6);
+        }
+    }
+}
@@ -1,4 +1,14 @@
+pub mod barnes_hut;
+pub mod collision;
+pub mod conics;
+pub mod diagnostics;
+pub mod encounter;
 pub mod force;
 pub mod gravity;
+pub mod integrator;
+pub mod lagrange;
 pub mod motion;
+pub mod orbit;
+pub mod parallel;
+pub mod soi;
 pub mod universe;
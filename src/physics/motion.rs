@@ -1,3 +1,5 @@
+use crate::physics::integrator;
+use crate::physics::integrator::{AccelerationField, Integrator};
 use hecs::World;
 use vecmath;
 
@@ -35,7 +37,12 @@ pub fn distance_between(lhs: &Position, rhs: &Position) -> f64 {
     f64::sqrt((rhs[0] - lhs[0]) * (rhs[0] - lhs[0]) + (rhs[1] - lhs[1]) * (rhs[1] - lhs[1]))
 }
 
-pub struct MotionSystem {}
+pub struct MotionSystem {
+    integrator: Integrator,
+    step_size: f64,
+    /// accelerations of bodies were evaluated at their current positions by the last step
+    accelerations_current: bool,
+}
 
 impl Default for MotionSystem {
     fn default() -> Self {
@@ -45,19 +52,134 @@ impl Default for MotionSystem {
 
 impl MotionSystem {
     pub fn new() -> Self {
-        MotionSystem {}
+        MotionSystem {
+            integrator: Integrator::default(),
+            step_size: 0.0,
+            accelerations_current: false,
+        }
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+        self.step_size = 0.0;
+        self.accelerations_current = false;
+    }
+
+    /// evaluate accelerations before the next step, e.g. after bodies collided
+    pub fn invalidate_accelerations(&mut self) {
+        self.accelerations_current = false;
+    }
+
+    /// size of the last step in simulated seconds
+    ///
+    /// adaptive integrators report the size they are going to try next.
+    pub fn step_size(&self) -> f64 {
+        self.step_size
+    }
+
+    /// advance bodies by dt, re-evaluating accelerations as the integrator requires
+    ///
+    /// returns the number of steps made
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        dt: f64,
+        field: &mut impl AccelerationField,
+    ) -> usize {
+        // only the leapfrog ends its step with accelerations at the final positions
+        let accelerations_current = self.accelerations_current;
+        self.accelerations_current = self.integrator == Integrator::VelocityVerlet;
+        match self.integrator {
+            Integrator::DormandPrince { tolerance } => integrator::adaptive_dormand_prince(
+                world,
+                dt,
+                tolerance,
+                &mut self.step_size,
+                field,
+            ),
+            Integrator::VelocityVerlet => {
+                integrator::velocity_verlet(world, dt, accelerations_current, field);
+                self.step_size = dt;
+                1
+            }
+            integrator => {
+                integrator.step(world, dt, field);
+                self.step_size = dt;
+                1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::integrator::tests::CentralField;
+    use std::f64::consts::PI;
+
+    /// central field counting evaluations
+    struct CountingField {
+        evaluations: usize,
     }
 
-    pub fn update(&mut self, world: &mut World, dt: f64) {
-        for (_id, (motion,)) in &mut world.query::<(&mut Motion,)>() {
-            self.advance(motion, dt);
+    impl AccelerationField for CountingField {
+        fn evaluate(&mut self, world: &mut World) {
+            self.evaluations += 1;
+            CentralField {}.evaluate(world);
         }
     }
 
-    fn advance(&self, motion: &mut Motion, dt: f64) {
-        let da = vecmath::vec2_scale(motion.acceleration, dt);
-        motion.velocity = vecmath::vec2_add(motion.velocity, da);
-        let dv = vecmath::vec2_scale(motion.velocity, dt);
-        motion.position = vecmath::vec2_add(motion.position, dv);
+    #[test]
+    fn leapfrog_evaluates_accelerations_once_per_step() {
+        let spawn = |world: &mut World| {
+            world.spawn((Motion::new_position_velocity([1.0, 0.0], [0.0, 1.0]),))
+        };
+        let mut world = World::new();
+        let body = spawn(&mut world);
+        let mut motion_system = MotionSystem::new();
+        motion_system.set_integrator(Integrator::VelocityVerlet);
+        let mut field = CountingField { evaluations: 0 };
+        for _ in 0..10 {
+            motion_system.update(&mut world, 0.01, &mut field);
+        }
+        assert_eq!(field.evaluations, 11);
+        motion_system.invalidate_accelerations();
+        motion_system.update(&mut world, 0.01, &mut field);
+        assert_eq!(field.evaluations, 13);
+
+        // same result as evaluating at the start of every step
+        let mut reference = World::new();
+        let reference_body = spawn(&mut reference);
+        for _ in 0..11 {
+            Integrator::VelocityVerlet.step(&mut reference, 0.01, &mut CentralField {});
+        }
+        let position = world.get::<&Motion>(body).unwrap().position;
+        let reference_position = reference.get::<&Motion>(reference_body).unwrap().position;
+        assert_eq!(position, reference_position);
+    }
+
+    #[test]
+    fn adaptive_steps_shrink_at_periapsis() {
+        let mut world = World::new();
+        // eccentric orbit with apoapsis at 1.9 and periapsis at 0.1
+        let apoapsis_speed = f64::sqrt(0.1 / 1.9);
+        let body = world.spawn((Motion::new_position_velocity(
+            [-1.9, 0.0],
+            [0.0, -apoapsis_speed],
+        ),));
+        let mut motion_system = MotionSystem::new();
+        motion_system.set_integrator(Integrator::DormandPrince { tolerance: 1.0e-10 });
+
+        let period = 2.0 * PI;
+        motion_system.update(&mut world, period / 4.0, &mut CentralField {});
+        let apoapsis_step = motion_system.step_size();
+        motion_system.update(&mut world, period / 4.0, &mut CentralField {});
+        let periapsis_step = motion_system.step_size();
+        motion_system.update(&mut world, period / 2.0, &mut CentralField {});
+
+        assert!(periapsis_step < apoapsis_step / 10.0);
+        let motion = world.get::<&Motion>(body).unwrap();
+        let error = vecmath::vec2_len(vecmath::vec2_sub(motion.position, [-1.9, 0.0]));
+        assert!(error < 1.0e-6, "orbit must close, error {}", error);
     }
 }
@@ -0,0 +1,486 @@
+use crate::core::tracking::TrackingComponent;
+use crate::physics::gravity::{MassComponent, G};
+use crate::physics::motion::{Motion, Position, Velocity};
+use hecs::{Entity, Without, World};
+use std::f64::consts::PI;
+
+/// orbits with the eccentricity closer to one than this are treated as parabolic
+const PARABOLIC_MARGIN: f64 = 1.0e-6;
+
+/// keplerian elements of an orbit in a plane
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrbitalElements {
+    /// meters. negative for hyperbolic orbits
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    /// angle between x axis and periapsis in radians
+    pub argument_of_periapsis: f64,
+    /// angle between periapsis and the body in radians
+    pub true_anomaly: f64,
+    /// direction of motion. counter-clockwise when false
+    pub clockwise: bool,
+}
+
+impl OrbitalElements {
+    /// osculating elements of a body with position and velocity relative to the attractor
+    pub fn from_state(position: Position, velocity: Velocity, mu: f64) -> Self {
+        let r = vecmath::vec2_len(position);
+        let v2 = vecmath::vec2_dot(velocity, velocity);
+        let radial = vecmath::vec2_dot(position, velocity);
+        let eccentricity_vector = vecmath::vec2_scale(
+            vecmath::vec2_sub(
+                vecmath::vec2_scale(position, v2 - mu / r),
+                vecmath::vec2_scale(velocity, radial),
+            ),
+            1.0 / mu,
+        );
+        let clockwise = vecmath::vec2_cross(position, velocity) < 0.0;
+        let argument_of_periapsis = f64::atan2(eccentricity_vector[1], eccentricity_vector[0]);
+        let direction = if clockwise { -1.0 } else { 1.0 };
+        let angle = f64::atan2(position[1], position[0]) - argument_of_periapsis;
+        OrbitalElements {
+            semi_major_axis: 1.0 / (2.0 / r - v2 / mu),
+            eccentricity: vecmath::vec2_len(eccentricity_vector),
+            argument_of_periapsis,
+            true_anomaly: f64::atan2(direction * angle.sin(), angle.cos()),
+            clockwise,
+        }
+    }
+
+    /// distance of the closest approach to the attractor
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    /// distance of the farthest point. none for open orbits
+    pub fn apoapsis(&self) -> Option<f64> {
+        if self.eccentricity < 1.0 {
+            Some(self.semi_major_axis * (1.0 + self.eccentricity))
+        } else {
+            None
+        }
+    }
+
+    /// orbital period in seconds. none for open orbits
+    pub fn period(&self, mu: f64) -> Option<f64> {
+        if self.eccentricity < 1.0 {
+            Some(2.0 * PI * f64::sqrt(self.semi_major_axis.powi(3) / mu))
+        } else {
+            None
+        }
+    }
+
+    /// position and velocity relative to the attractor
+    ///
+    /// mu is the gravitational parameter G * (M + m) of the attractor and the body
+    pub fn state(&self, mu: f64) -> (Position, Velocity) {
+        let e = self.eccentricity;
+        let nu = self.true_anomaly;
+        let speed = f64::sqrt(mu / self.semi_latus_rectum());
+        let direction = if self.clockwise { -1.0 } else { 1.0 };
+        let velocity = [-speed * nu.sin(), direction * speed * (e + nu.cos())];
+        (
+            self.position_at(nu),
+            rotate(velocity, self.argument_of_periapsis),
+        )
+    }
+
+    /// position relative to the attractor at the given true anomaly
+    pub fn position_at(&self, true_anomaly: f64) -> Position {
+        let r = self.semi_latus_rectum() / (1.0 + self.eccentricity * true_anomaly.cos());
+        // perifocal frame: x points to periapsis, mirrored for clockwise orbits
+        let direction = if self.clockwise { -1.0 } else { 1.0 };
+        let position = [r * true_anomaly.cos(), direction * r * true_anomaly.sin()];
+        rotate(position, self.argument_of_periapsis)
+    }
+
+    /// points of the orbit relative to the attractor starting at periapsis
+    ///
+    /// open orbits are cut at the given distance from the attractor.
+    pub fn path(&self, points: usize, max_distance: f64) -> Vec<Position> {
+        let e = self.eccentricity;
+        let p = self.semi_latus_rectum();
+        let (from, to) = if e < 1.0 && self.apoapsis().unwrap() <= max_distance {
+            (0.0, 2.0 * PI)
+        } else {
+            // true anomaly where the orbit reaches the max distance
+            let limit = ((p / max_distance - 1.0) / e).clamp(-1.0, 1.0).acos();
+            (-limit, limit)
+        };
+        (0..points)
+            .map(|i| {
+                let nu = from + (to - from) * i as f64 / (points - 1).max(1) as f64;
+                self.position_at(nu)
+            })
+            .collect()
+    }
+
+    /// elements after dt seconds of keplerian motion
+    ///
+    /// none for nearly parabolic orbits, where kepler's equation loses its precision,
+    /// and for elements that aren't finite.
+    pub fn propagate(&self, mu: f64, dt: f64) -> Option<Self> {
+        let (a, e) = (self.semi_major_axis.abs(), self.eccentricity);
+        if (e - 1.0).abs() < PARABOLIC_MARGIN || !a.is_finite() || !e.is_finite() {
+            return None;
+        }
+        let mean_motion = f64::sqrt(mu / (a * a * a));
+        let mean_anomaly = mean_anomaly_from_true(self.true_anomaly, e) + mean_motion * dt;
+        let true_anomaly = true_anomaly_from_mean(mean_anomaly, e);
+        true_anomaly.is_finite().then_some(OrbitalElements {
+            true_anomaly,
+            ..*self
+        })
+    }
+
+    fn semi_latus_rectum(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity * self.eccentricity)
+    }
+}
+
+fn rotate(v: [f64; 2], angle: f64) -> [f64; 2] {
+    let (sin, cos) = angle.sin_cos();
+    [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos]
+}
+
+/// mean anomaly of the body at the given true anomaly
+pub fn mean_anomaly_from_true(true_anomaly: f64, eccentricity: f64) -> f64 {
+    let e = eccentricity;
+    let half = true_anomaly / 2.0;
+    if e < 1.0 {
+        let anomaly =
+            2.0 * f64::atan2((1.0 - e).sqrt() * half.sin(), (1.0 + e).sqrt() * half.cos());
+        anomaly - e * anomaly.sin()
+    } else {
+        let anomaly = 2.0 * (((e - 1.0) / (e + 1.0)).sqrt() * half.tan()).atanh();
+        e * anomaly.sinh() - anomaly
+    }
+}
+
+/// solve kepler's equation for the true anomaly
+///
+/// handles elliptic (e < 1) and hyperbolic (e > 1) orbits.
+pub fn true_anomaly_from_mean(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let e = eccentricity;
+    if e < 1.0 {
+        let m = mean_anomaly.rem_euclid(2.0 * PI);
+        let mut anomaly = if e > 0.8 { PI } else { m };
+        for _ in 0..50 {
+            let delta = (anomaly - e * anomaly.sin() - m) / (1.0 - e * anomaly.cos());
+            anomaly -= delta;
+            if delta.abs() < 1.0e-14 {
+                break;
+            }
+        }
+        2.0 * f64::atan2(
+            (1.0 + e).sqrt() * (anomaly / 2.0).sin(),
+            (1.0 - e).sqrt() * (anomaly / 2.0).cos(),
+        )
+    } else {
+        let m = mean_anomaly;
+        let mut anomaly = (2.0 * m / e).asinh();
+        for _ in 0..50 {
+            let delta = (e * anomaly.sinh() - anomaly - m) / (e * anomaly.cosh() - 1.0);
+            anomaly -= delta;
+            if delta.abs() < 1.0e-14 {
+                break;
+            }
+        }
+        2.0 * f64::atan2(
+            (e + 1.0).sqrt() * (anomaly / 2.0).sinh(),
+            (e - 1.0).sqrt() * (anomaly / 2.0).cosh(),
+        )
+    }
+}
+
+/// osculating orbit of a body around its dominant attractor
+pub struct OrbitComponent {
+    pub attractor: Entity,
+    /// gravitational parameter G * (M + m) of the attractor and the body
+    pub mu: f64,
+    pub elements: OrbitalElements,
+}
+
+impl OrbitComponent {
+    pub fn new(attractor: Entity, mu: f64, elements: OrbitalElements) -> Self {
+        OrbitComponent {
+            attractor,
+            mu,
+            elements,
+        }
+    }
+
+    pub fn period(&self) -> Option<f64> {
+        self.elements.period(self.mu)
+    }
+}
+
+/// orbit of the body around its dominant attractor among the bodies
+///
+/// the dominant attractor of a body is the heavier body pulling it the strongest.
+/// none for the heaviest body.
+fn dominant_orbit(bodies: &[(Entity, f64, Motion)], body: Entity) -> Option<OrbitComponent> {
+    let (_, mass, motion) = bodies.iter().find(|(id, _, _)| *id == body)?;
+    let (_, attractor, attractor_mass, attractor_motion) = bodies
+        .iter()
+        .filter(|(other, other_mass, _)| *other != body && other_mass > mass)
+        .map(|(other, other_mass, other_motion)| {
+            let offset = vecmath::vec2_sub(motion.position, other_motion.position);
+            let pull = other_mass / vecmath::vec2_dot(offset, offset);
+            (pull, other, other_mass, other_motion)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+    let mu = G * (attractor_mass + mass);
+    let elements = OrbitalElements::from_state(
+        vecmath::vec2_sub(motion.position, attractor_motion.position),
+        vecmath::vec2_sub(motion.velocity, attractor_motion.velocity),
+        mu,
+    );
+    Some(OrbitComponent::new(*attractor, mu, elements))
+}
+
+fn bodies(world: &World) -> Vec<(Entity, f64, Motion)> {
+    world
+        .query::<(&MassComponent, &Motion)>()
+        .iter()
+        .map(|(id, (mass, motion))| (id, mass.mass, *motion))
+        .collect()
+}
+
+/// orbits of all bodies around their dominant attractors
+///
+/// every body is compared with every other one, so it's too slow to run every frame
+/// for large systems.
+pub fn orbits(world: &World) -> Vec<(Entity, OrbitComponent)> {
+    let bodies = bodies(world);
+    bodies
+        .iter()
+        .filter_map(|(id, _, _)| dominant_orbit(&bodies, *id).map(|orbit| (*id, orbit)))
+        .collect()
+}
+
+/// keeps the orbit of the tracked body around its dominant attractor up to date
+///
+/// only the tracked body is shown in the hud, other bodies have no orbit component.
+pub struct OrbitSystem {}
+
+impl Default for OrbitSystem {
+    fn default() -> Self {
+        OrbitSystem::new()
+    }
+}
+
+impl OrbitSystem {
+    pub fn new() -> Self {
+        OrbitSystem {}
+    }
+
+    pub fn update(&self, world: &mut World) {
+        let stale: Vec<Entity> = world
+            .query::<Without<&OrbitComponent, &TrackingComponent>>()
+            .iter()
+            .map(|(id, _)| id)
+            .collect();
+        for id in stale {
+            world.remove_one::<OrbitComponent>(id).unwrap();
+        }
+
+        let tracked: Vec<Entity> = world
+            .query::<&TrackingComponent>()
+            .iter()
+            .map(|(id, _)| id)
+            .collect();
+        if tracked.is_empty() {
+            return;
+        }
+        let bodies = bodies(world);
+        for id in tracked {
+            match dominant_orbit(&bodies, id) {
+                Some(orbit) => match world.query_one_mut::<&mut OrbitComponent>(id) {
+                    Ok(current) => *current = orbit,
+                    Err(_) => world.insert_one(id, orbit).unwrap(),
+                },
+                None => {
+                    let _ = world.remove_one::<OrbitComponent>(id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(eccentricity: f64, true_anomaly: f64, clockwise: bool) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: 2.0,
+            eccentricity,
+            argument_of_periapsis: PI / 2.0,
+            true_anomaly,
+            clockwise,
+        }
+    }
+
+    fn assert_close(actual: [f64; 2], expected: [f64; 2]) {
+        let error = vecmath::vec2_len(vecmath::vec2_sub(actual, expected));
+        assert!(error < 1.0e-12, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn circular_orbit_state() {
+        let (position, velocity) = elements(0.0, 0.0, false).state(2.0);
+        assert_close(position, [0.0, 2.0]);
+        assert_close(velocity, [-1.0, 0.0]);
+
+        let (position, velocity) = elements(0.0, 0.0, true).state(2.0);
+        assert_close(position, [0.0, 2.0]);
+        assert_close(velocity, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn periapsis_speed_follows_vis_viva() {
+        let (position, velocity) = elements(0.5, 0.0, false).state(1.0);
+        let r = vecmath::vec2_len(position);
+        let expected_speed = f64::sqrt(2.0 / r - 1.0 / 2.0);
+        assert!((r - 1.0).abs() < 1.0e-12);
+        assert!((vecmath::vec2_len(velocity) - expected_speed).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn elements_from_state() {
+        for clockwise in [false, true] {
+            let expected = OrbitalElements {
+                semi_major_axis: 2.0,
+                eccentricity: 0.3,
+                argument_of_periapsis: 1.0,
+                true_anomaly: -2.0,
+                clockwise,
+            };
+            let (position, velocity) = expected.state(3.0);
+            let elements = OrbitalElements::from_state(position, velocity, 3.0);
+            assert!((elements.semi_major_axis - 2.0).abs() < 1.0e-12);
+            assert!((elements.eccentricity - 0.3).abs() < 1.0e-12);
+            assert!((elements.argument_of_periapsis - 1.0).abs() < 1.0e-12);
+            assert!((elements.true_anomaly + 2.0).abs() < 1.0e-12);
+            assert_eq!(elements.clockwise, clockwise);
+            assert!((elements.periapsis() - 1.4).abs() < 1.0e-12);
+            assert!((elements.apoapsis().unwrap() - 2.6).abs() < 1.0e-12);
+        }
+    }
+
+    #[test]
+    fn orbit_around_dominant_attractor() {
+        let mut world = World::new();
+        let star = world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let planet = world.spawn((
+            MassComponent::new(1.0e24),
+            Motion::new_position_velocity([1.0e11, 0.0], [0.0, 3.0e4]),
+        ));
+        let moon = world.spawn((
+            MassComponent::new(1.0e22),
+            Motion::new_position_velocity([1.0e11 + 1.0e7, 0.0], [0.0, 3.1e4]),
+        ));
+        let orbits = orbits(&world);
+
+        assert_eq!(orbits.len(), 2);
+        let orbit = |body: Entity| &orbits.iter().find(|(id, _)| *id == body).unwrap().1;
+        assert_eq!(orbit(planet).attractor, star);
+        assert_eq!(orbit(moon).attractor, planet);
+        assert!(orbit(moon).elements.eccentricity < 1.0);
+        assert!(orbit(moon).period().is_some());
+    }
+
+    #[test]
+    fn only_tracked_body_has_an_orbit() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let planet = world.spawn((
+            MassComponent::new(1.0e24),
+            Motion::new_position_velocity([1.0e11, 0.0], [0.0, 3.0e4]),
+            TrackingComponent::default(),
+        ));
+        let moon = world.spawn((
+            MassComponent::new(1.0e22),
+            Motion::new_position_velocity([1.0e11 + 1.0e7, 0.0], [0.0, 3.1e4]),
+        ));
+        let orbits = OrbitSystem::new();
+        orbits.update(&mut world);
+        assert!(world.get::<&OrbitComponent>(planet).is_ok());
+        assert!(world.get::<&OrbitComponent>(moon).is_err());
+
+        world.remove_one::<TrackingComponent>(planet).unwrap();
+        world
+            .insert_one(moon, TrackingComponent::default())
+            .unwrap();
+        orbits.update(&mut world);
+        assert!(world.get::<&OrbitComponent>(planet).is_err());
+        assert_eq!(
+            world.get::<&OrbitComponent>(moon).unwrap().attractor,
+            planet
+        );
+    }
+
+    #[test]
+    fn path_follows_the_orbit() {
+        let closed = elements(0.5, 0.0, false);
+        let path = closed.path(9, f64::INFINITY);
+        assert_eq!(path.len(), 9);
+        assert_close(path[0], closed.position_at(0.0));
+        assert_close(path[8], path[0]);
+        assert_close(path[4], closed.position_at(PI));
+
+        let open = OrbitalElements {
+            semi_major_axis: -1.0,
+            eccentricity: 2.0,
+            ..closed
+        };
+        let path = open.path(16, 10.0);
+        assert!((vecmath::vec2_len(path[0]) - 10.0).abs() < 1.0e-9);
+        assert!((vecmath::vec2_len(path[15]) - 10.0).abs() < 1.0e-9);
+        assert!(path
+            .iter()
+            .all(|point| vecmath::vec2_len(*point) <= 10.0 + 1.0e-9));
+    }
+
+    #[test]
+    fn true_anomaly_of_apsides() {
+        assert!(true_anomaly_from_mean(0.0, 0.7).abs() < 1.0e-12);
+        assert!((true_anomaly_from_mean(PI, 0.7) - PI).abs() < 1.0e-12);
+        assert!(true_anomaly_from_mean(0.0, 1.5).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn propagate_to_apoapsis_and_back() {
+        let periapsis = elements(0.5, 0.0, true);
+        let period = periapsis.period(1.0).unwrap();
+        let apoapsis = periapsis.propagate(1.0, period / 2.0).unwrap();
+        assert!((apoapsis.true_anomaly.abs() - PI).abs() < 1.0e-9);
+        let back = periapsis.propagate(1.0, 1000.0 * period).unwrap();
+        assert!(back.true_anomaly.abs() < 1.0e-9);
+
+        let open = OrbitalElements {
+            semi_major_axis: -1.0,
+            eccentricity: 2.0,
+            true_anomaly: 1.0,
+            ..periapsis
+        };
+        let mean = mean_anomaly_from_true(1.0, 2.0);
+        assert!((true_anomaly_from_mean(mean, 2.0) - 1.0).abs() < 1.0e-12);
+        assert!(open.propagate(1.0, 10.0).unwrap().true_anomaly > 1.0);
+
+        let parabolic = OrbitalElements {
+            eccentricity: 1.0,
+            ..open
+        };
+        assert_eq!(parabolic.propagate(1.0, 10.0), None);
+    }
+
+    #[test]
+    fn true_anomaly_solves_kepler_equation() {
+        let e: f64 = 0.3;
+        let nu = true_anomaly_from_mean(1.0, e);
+        let eccentric = 2.0 * f64::atan(((1.0 - e) / (1.0 + e)).sqrt() * (nu / 2.0).tan());
+        assert!((eccentric - e * eccentric.sin() - 1.0).abs() < 1.0e-12);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::physics::force::Force;
+use crate::physics::gravity::GravityCalculation;
+use std::ops::Range;
+use std::thread;
+
+/// bodies per block
+///
+/// pair forces are calculated in tiles of two blocks and summed up in a fixed order,
+/// so results don't depend on the number of threads.
+const BLOCK_SIZE: usize = 64;
+
+/// fewer bodies are calculated by the calling thread
+///
+/// threads are spawned for every evaluation, several times per step with multi-stage
+/// integrators. for small systems that costs more than the parallel sum saves.
+const MIN_PARALLEL_BODIES: usize = 512;
+
+/// forces acting on bodies of the tile row and column blocks
+type TileForces = (Vec<Force>, Vec<Force>);
+
+/// exact gravitational forces calculated by several threads
+///
+/// every pair of bodies is visited once, the pair force is applied to both bodies.
+/// zero threads means one thread per available core.
+/// systems smaller than MIN_PARALLEL_BODIES are calculated by the calling thread in the same order.
+pub fn forces(bodies: &[GravityCalculation], threads: usize) -> Vec<Force> {
+    let threads = if threads == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let blocks = bodies.len().div_ceil(BLOCK_SIZE);
+    let tiles: Vec<(usize, usize)> = (0..blocks)
+        .flat_map(|row| (row..blocks).map(move |column| (row, column)))
+        .collect();
+    let mut partials: Vec<TileForces> = vec![(vec![], vec![]); tiles.len()];
+
+    if threads == 1 || bodies.len() < MIN_PARALLEL_BODIES {
+        for (tile, partial) in tiles.iter().zip(partials.iter_mut()) {
+            *partial = tile_forces(bodies, *tile);
+        }
+    } else {
+        let tiles_per_thread = usize::max(1, tiles.len().div_ceil(threads));
+        thread::scope(|scope| {
+            for (tile_chunk, partial_chunk) in tiles
+                .chunks(tiles_per_thread)
+                .zip(partials.chunks_mut(tiles_per_thread))
+            {
+                scope.spawn(move || {
+                    for (tile, partial) in tile_chunk.iter().zip(partial_chunk.iter_mut()) {
+                        *partial = tile_forces(bodies, *tile);
+                    }
+                });
+            }
+        });
+    }
+
+    let mut forces: Vec<Force> = vec![[0.0, 0.0]; bodies.len()];
+    for ((row, column), (row_forces, column_forces)) in tiles.iter().zip(partials) {
+        accumulate(&mut forces[block(*row, bodies.len())], &row_forces);
+        if row != column {
+            accumulate(&mut forces[block(*column, bodies.len())], &column_forces);
+        }
+    }
+    forces
+}
+
+fn block(index: usize, bodies: usize) -> Range<usize> {
+    index * BLOCK_SIZE..usize::min((index + 1) * BLOCK_SIZE, bodies)
+}
+
+fn accumulate(forces: &mut [Force], partial: &[Force]) {
+    for (force, part) in forces.iter_mut().zip(partial) {
+        *force = vecmath::vec2_add(*force, *part);
+    }
+}
+
+fn tile_forces(bodies: &[GravityCalculation], (row, column): (usize, usize)) -> TileForces {
+    let rows = &bodies[block(row, bodies.len())];
+    let columns = &bodies[block(column, bodies.len())];
+    let mut row_forces: Vec<Force> = vec![[0.0, 0.0]; rows.len()];
+    let mut column_forces: Vec<Force> = vec![[0.0, 0.0]; columns.len()];
+
+    for (i, x) in rows.iter().enumerate() {
+        // diagonal tiles hold each pair twice, visit only the upper triangle
+        let first = if row == column { i + 1 } else { 0 };
+        for (j, y) in columns.iter().enumerate().skip(first) {
+            let force = x.acceleration(y);
+            row_forces[i] = vecmath::vec2_add(row_forces[i], force);
+            if row == column {
+                row_forces[j] = vecmath::vec2_sub(row_forces[j], force);
+            } else {
+                column_forces[j] = vecmath::vec2_sub(column_forces[j], force);
+            }
+        }
+    }
+    (row_forces, column_forces)
+}
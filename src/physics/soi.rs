@@ -0,0 +1,292 @@
+use crate::core::name::name_of;
+use crate::physics::gravity::{Mass, MassComponent};
+use crate::physics::motion::{distance_between, Motion, Position};
+use hecs::{Entity, World};
+use std::collections::BTreeSet;
+use std::mem;
+
+/// laplace sphere of influence of the body around its primary
+///
+/// the radius is calculated from the current distance to the primary rather than
+/// the semi-major axis, so spheres of bodies on eccentric orbits grow and shrink along the orbit.
+pub struct SphereOfInfluenceComponent {
+    /// none for the heaviest body
+    pub primary: Option<Entity>,
+    /// meters. infinite for the heaviest body
+    pub radius: f64,
+}
+
+impl SphereOfInfluenceComponent {
+    pub fn new(primary: Option<Entity>, radius: f64) -> Self {
+        SphereOfInfluenceComponent { primary, radius }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Crossing {
+    Entered,
+    Left,
+}
+
+/// body crossing the boundary of the sphere of influence of another body
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoiEvent {
+    pub body: Entity,
+    pub sphere: Entity,
+    pub crossing: Crossing,
+    pub time: f64,
+}
+
+impl SoiEvent {
+    /// human readable description with names of bodies
+    pub fn describe(&self, world: &World) -> String {
+        let crossing = match self.crossing {
+            Crossing::Entered => "entered",
+            Crossing::Left => "left",
+        };
+        format!(
+            "{} {} sphere of influence of {} at {:.0} s",
+            name_of(world, self.body),
+            crossing,
+            name_of(world, self.sphere),
+            self.time
+        )
+    }
+}
+
+/// keeps spheres of influence of bodies up to date and reports bodies crossing them
+///
+/// a body is inside the sphere of influence of its primary, the primary of its primary and so on.
+/// events are kept until they are taken. every body is compared with the heavier ones,
+/// so spheres are only updated while crossings are reported or spheres are shown.
+pub struct SphereOfInfluenceSystem {
+    /// report crossings every update
+    enabled: bool,
+    /// keep spheres up to date for the overlay
+    shown: bool,
+    inside: Option<BTreeSet<(Entity, Entity)>>,
+    events: Vec<SoiEvent>,
+}
+
+impl Default for SphereOfInfluenceSystem {
+    fn default() -> Self {
+        SphereOfInfluenceSystem::new()
+    }
+}
+
+impl SphereOfInfluenceSystem {
+    pub fn new() -> Self {
+        SphereOfInfluenceSystem {
+            enabled: false,
+            shown: false,
+            inside: None,
+            events: vec![],
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    /// true when spheres are updated every frame
+    pub fn active(&self) -> bool {
+        self.enabled || self.shown
+    }
+
+    /// crossings found since the last call
+    pub fn take_events(&mut self) -> Vec<SoiEvent> {
+        mem::take(&mut self.events)
+    }
+
+    pub fn update(&mut self, world: &mut World, time: f64) {
+        if !self.active() {
+            self.inside = None;
+            return;
+        }
+        let inside = self.refresh(world);
+        if !self.enabled {
+            // crossings while nobody listens are not reported once logging starts
+            self.inside = None;
+            return;
+        }
+
+        if let Some(previous) = self.inside.as_ref() {
+            let exists = |(body, sphere): &&(Entity, Entity)| {
+                world.contains(*body) && world.contains(*sphere)
+            };
+            for (body, sphere) in inside.difference(previous).filter(exists) {
+                self.events.push(SoiEvent {
+                    body: *body,
+                    sphere: *sphere,
+                    crossing: Crossing::Entered,
+                    time,
+                });
+            }
+            for (body, sphere) in previous.difference(&inside).filter(exists) {
+                self.events.push(SoiEvent {
+                    body: *body,
+                    sphere: *sphere,
+                    crossing: Crossing::Left,
+                    time,
+                });
+            }
+        }
+        self.inside = Some(inside);
+    }
+
+    /// update spheres of influence of all bodies once, without reporting crossings
+    ///
+    /// returns pairs of bodies and spheres of influence they are in.
+    pub fn refresh(&self, world: &mut World) -> BTreeSet<(Entity, Entity)> {
+        let mut ids = vec![];
+        let mut bodies = vec![];
+        for (id, (mass, motion)) in &mut world.query::<(&MassComponent, &Motion)>() {
+            ids.push(id);
+            bodies.push((mass.mass, motion.position));
+        }
+        let spheres = spheres_of_influence(&bodies);
+
+        let mut inside = BTreeSet::new();
+        for (b, (primary, _)) in spheres.iter().enumerate() {
+            let mut primary = *primary;
+            while let Some(s) = primary {
+                if spheres[s].1.is_finite() {
+                    inside.insert((ids[b], ids[s]));
+                }
+                primary = spheres[s].0;
+            }
+        }
+        let mut spawned = vec![];
+        for (i, (primary, radius)) in spheres.into_iter().enumerate() {
+            let primary = primary.map(|primary| ids[primary]);
+            match world.query_one_mut::<&mut SphereOfInfluenceComponent>(ids[i]) {
+                Ok(sphere) => {
+                    sphere.primary = primary;
+                    sphere.radius = radius;
+                }
+                Err(_) => spawned.push((ids[i], SphereOfInfluenceComponent::new(primary, radius))),
+            }
+        }
+        for (id, sphere) in spawned {
+            world.insert_one(id, sphere).unwrap();
+        }
+        inside
+    }
+}
+
+/// primary of every body and the radius of its laplace sphere of influence
+///
+/// the primary of a body is the heavier body with the smallest sphere of influence containing it.
+/// the heaviest body has no primary and an infinite sphere of influence.
+/// radii use the current distance between the body and its primary.
+/// every body is compared with the heavier ones, O(n^2) in the worst case.
+pub fn spheres_of_influence(bodies: &[(Mass, Position)]) -> Vec<(Option<usize>, f64)> {
+    let mut spheres = vec![(None, f64::INFINITY); bodies.len()];
+    let mut processed: Vec<usize> = Vec::with_capacity(bodies.len());
+    for i in by_mass(bodies) {
+        let (mass, position) = bodies[i];
+        let primary = processed
+            .iter()
+            .filter(|j| distance_between(&position, &bodies[**j].1) < spheres[**j].1)
+            .min_by(|a, b| spheres[**a].1.total_cmp(&spheres[**b].1))
+            .copied();
+        if let Some(primary) = primary {
+            let (primary_mass, primary_position) = bodies[primary];
+            let distance = distance_between(&position, &primary_position);
+            spheres[i] = (Some(primary), distance * (mass / primary_mass).powf(0.4));
+        }
+        processed.push(i);
+    }
+    spheres
+}
+
+/// indices of bodies from the heaviest to the lightest. primaries come before their satellites
+pub fn by_mass(bodies: &[(Mass, Position)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_by(|a, b| bodies[*b].0.total_cmp(&bodies[*a].0));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_spheres_of_influence() {
+        let bodies = [
+            (1.0e22, [1.0e9 + 5.0e6, 0.0]),
+            (1.0e30, [0.0, 0.0]),
+            (1.0e25, [1.0e9, 0.0]),
+            (1.0e22, [-1.0e9, 0.0]),
+        ];
+        let spheres = spheres_of_influence(&bodies);
+        assert_eq!(spheres[1], (None, f64::INFINITY));
+        assert_eq!(spheres[2].0, Some(1));
+        assert!((spheres[2].1 - 1.0e9 * 1.0e-2).abs() < 1.0);
+        assert_eq!(spheres[0].0, Some(2));
+        assert_eq!(spheres[3].0, Some(1));
+    }
+
+    #[test]
+    fn crossing_events() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let planet = world.spawn((MassComponent::new(1.0e25), Motion::position([1.0e9, 0.0])));
+        let moon = world.spawn((
+            MassComponent::new(1.0e22),
+            Motion::position([1.0e9 + 2.0e7, 0.0]),
+        ));
+        let mut spheres = SphereOfInfluenceSystem::new();
+        spheres.set_enabled(true);
+        spheres.update(&mut world, 0.0);
+        assert!(spheres.take_events().is_empty());
+        let radius = world
+            .get::<&SphereOfInfluenceComponent>(planet)
+            .unwrap()
+            .radius;
+        assert!((radius - 1.0e7).abs() < 1.0);
+
+        world.get::<&mut Motion>(moon).unwrap().position = [1.0e9 + 5.0e6, 0.0];
+        spheres.update(&mut world, 1.0);
+        let entered = SoiEvent {
+            body: moon,
+            sphere: planet,
+            crossing: Crossing::Entered,
+            time: 1.0,
+        };
+        assert_eq!(spheres.take_events(), vec![entered]);
+        assert!(entered
+            .describe(&world)
+            .contains("entered sphere of influence"));
+        assert_eq!(
+            world
+                .get::<&SphereOfInfluenceComponent>(moon)
+                .unwrap()
+                .primary,
+            Some(planet)
+        );
+
+        world.get::<&mut Motion>(moon).unwrap().position = [1.0e9 + 2.0e7, 0.0];
+        spheres.update(&mut world, 2.0);
+        assert_eq!(spheres.take_events()[0].crossing, Crossing::Left);
+    }
+
+    #[test]
+    fn spheres_are_only_updated_when_used() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let planet = world.spawn((MassComponent::new(1.0e25), Motion::position([1.0e9, 0.0])));
+        let mut spheres = SphereOfInfluenceSystem::new();
+        spheres.update(&mut world, 0.0);
+        assert!(world.get::<&SphereOfInfluenceComponent>(planet).is_err());
+
+        spheres.set_shown(true);
+        spheres.update(&mut world, 1.0);
+        assert!(world.get::<&SphereOfInfluenceComponent>(planet).is_ok());
+        assert!(spheres.take_events().is_empty());
+    }
+}
@@ -1,20 +1,87 @@
+use crate::physics::collision::{CollisionPolicy, CollisionSystem};
+use crate::physics::conics::PatchedConicsSystem;
+use crate::physics::diagnostics::DiagnosticsSystem;
+use crate::physics::encounter::{Encounter, EncounterSystem};
 use crate::physics::force::ForceSystem;
-use crate::physics::gravity::GravitySystem;
+use crate::physics::gravity::{GravitySolver, GravitySystem};
+use crate::physics::integrator::{AccelerationField, Integrator};
 use crate::physics::motion::MotionSystem;
+use crate::physics::orbit::OrbitSystem;
+use crate::physics::soi::{SoiEvent, SphereOfInfluenceSystem};
 use hecs::World;
-use piston::input::UpdateArgs;
+use serde::Deserialize;
+use std::fs::File;
 
 const ACCELERATIONS: [f64; 14] = [
     1.0e1, 1.0e2, 2.5e2, 5.0e2, 7.5e2, 1.0e3, 2.5e3, 5.0e3, 7.5e3, 1.0e4, 2.5e4, 5.0e4, 7.5e4,
     1.0e5,
 ];
 
+/// how frame time is split into physics steps
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum Timestep {
+    /// a single step covering the whole frame time
+    #[default]
+    Frame,
+    /// steps of max_step simulated seconds, at most max_steps per frame
+    Fixed { max_step: f64, max_steps: usize },
+}
+
+impl Timestep {
+    /// fixed steps have to be positive and at least one of them has to fit a frame
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Timestep::Frame => true,
+            Timestep::Fixed {
+                max_step,
+                max_steps,
+            } => max_step > 0.0 && max_step.is_finite() && max_steps >= 1,
+        }
+    }
+}
+
+/// how bodies are moved
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum Propagation {
+    /// numerical integration of gravity between every pair of bodies
+    #[default]
+    NBody,
+    /// analytic kepler orbits around primaries of spheres of influence
+    PatchedConics,
+}
+
+/// systems evaluating accelerations of bodies for their current positions
+struct Dynamics {
+    gravity: GravitySystem,
+    force: ForceSystem,
+}
+
+impl AccelerationField for Dynamics {
+    fn evaluate(&mut self, world: &mut World) {
+        self.force.reset(world);
+        self.gravity.update(world);
+        self.force.update(world);
+    }
+}
+
 pub struct Universe {
     pub acceleration: f64,
     motion: MotionSystem,
-    gravity: GravitySystem,
-    force: ForceSystem,
+    dynamics: Dynamics,
+    propagation: Propagation,
+    conics: PatchedConicsSystem,
+    collisions: CollisionSystem,
+    encounters: EncounterSystem,
+    diagnostics: DiagnosticsSystem,
+    orbits: OrbitSystem,
+    spheres: SphereOfInfluenceSystem,
+    drift_alarm: bool,
     selected_acceleration: usize,
+    timestep: Timestep,
+    elapsed: f64,
+    accumulator: f64,
+    steps_per_frame: usize,
+    falling_behind: bool,
 }
 
 impl Default for Universe {
@@ -28,17 +95,142 @@ impl Universe {
         Universe {
             acceleration: ACCELERATIONS[9],
             motion: MotionSystem::default(),
-            gravity: GravitySystem::default(),
-            force: ForceSystem::default(),
+            dynamics: Dynamics {
+                gravity: GravitySystem::default(),
+                force: ForceSystem::default(),
+            },
+            propagation: Propagation::default(),
+            conics: PatchedConicsSystem::default(),
+            collisions: CollisionSystem::default(),
+            encounters: EncounterSystem::default(),
+            diagnostics: DiagnosticsSystem::default(),
+            orbits: OrbitSystem::default(),
+            spheres: SphereOfInfluenceSystem::default(),
+            drift_alarm: false,
             selected_acceleration: 9,
+            timestep: Timestep::default(),
+            elapsed: 0.0,
+            accumulator: 0.0,
+            steps_per_frame: 0,
+            falling_behind: false,
         }
     }
 
-    #[allow(dead_code)]
     pub fn acceleration(&self) -> f64 {
         self.acceleration
     }
 
+    pub fn paused(&self) -> bool {
+        self.acceleration <= 0.0
+    }
+
+    /// simulated seconds since the start
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.motion.set_integrator(integrator);
+    }
+
+    pub fn set_propagation(&mut self, propagation: Propagation) {
+        self.propagation = propagation;
+        self.motion.invalidate_accelerations();
+    }
+
+    pub fn set_gravity_solver(&mut self, solver: GravitySolver) {
+        self.dynamics.gravity.set_solver(solver);
+        self.motion.invalidate_accelerations();
+    }
+
+    /// softening length of bodies without their own softening
+    pub fn set_softening(&mut self, softening: f64) {
+        self.dynamics.gravity.set_softening(softening);
+        self.motion.invalidate_accelerations();
+        self.diagnostics.set_softening(softening);
+    }
+
+    /// report close encounters of bodies within the distance
+    pub fn set_encounter_distance(&mut self, distance: f64) {
+        self.encounters.set_distance(distance);
+    }
+
+    /// close encounters finished since the last call
+    pub fn take_encounters(&mut self) -> Vec<Encounter> {
+        self.encounters.take_finished()
+    }
+
+    pub fn diagnostics(&self) -> &DiagnosticsSystem {
+        &self.diagnostics
+    }
+
+    /// measure conserved quantities every frame
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics.set_enabled(enabled);
+    }
+
+    /// pause the simulation when relative drift of conserved quantities exceeds the threshold
+    pub fn set_drift_threshold(&mut self, threshold: f64) {
+        self.diagnostics.set_threshold(threshold);
+    }
+
+    /// write conserved quantities of every frame to a csv file
+    pub fn log_diagnostics(&mut self, file: File) {
+        self.diagnostics.log_to(file);
+    }
+
+    /// bodies that crossed spheres of influence since the last call
+    pub fn take_soi_events(&mut self) -> Vec<SoiEvent> {
+        self.spheres.take_events()
+    }
+
+    /// update spheres of influence every frame and report bodies crossing them
+    pub fn set_spheres_of_influence(&mut self, enabled: bool) {
+        self.spheres.set_enabled(enabled);
+    }
+
+    /// update spheres of influence every frame while they are shown
+    pub fn show_spheres_of_influence(&mut self, shown: bool) {
+        self.spheres.set_shown(shown);
+    }
+
+    /// update spheres of influence once, e.g. before looking up the primary of a body
+    pub fn refresh_spheres_of_influence(&self, world: &mut World) {
+        self.spheres.refresh(world);
+    }
+
+    /// true once the drift of conserved quantities exceeded the threshold
+    pub fn drift_alarm(&self) -> bool {
+        self.drift_alarm
+    }
+
+    pub fn set_collision_policy(&mut self, policy: CollisionPolicy) {
+        self.collisions.set_policy(policy);
+    }
+
+    pub fn set_timestep(&mut self, timestep: Timestep) {
+        self.timestep = timestep;
+        self.accumulator = 0.0;
+    }
+
+    /// number of physics steps made during the last frame
+    pub fn steps_per_frame(&self) -> usize {
+        self.steps_per_frame
+    }
+
+    /// current integration step in simulated seconds
+    pub fn step_size(&self) -> f64 {
+        match self.propagation {
+            Propagation::NBody => self.motion.step_size(),
+            Propagation::PatchedConics => self.conics.step_size(),
+        }
+    }
+
+    /// true when the last frame could not make all the steps it needed
+    pub fn falling_behind(&self) -> bool {
+        self.falling_behind
+    }
+
     pub fn change_acceleration(&mut self, new_acceleration: f64) {
         self.acceleration = new_acceleration;
     }
@@ -65,11 +257,170 @@ impl Universe {
         }
     }
 
-    pub fn update(&mut self, args: UpdateArgs, world: &mut World) {
-        let dt = args.dt * self.acceleration;
-        self.force.reset(world);
-        self.gravity.update(world);
-        self.force.update(world);
-        self.motion.update(world, dt);
+    /// advance the universe after frame_time real seconds passed
+    pub fn update(&mut self, frame_time: f64, world: &mut World) {
+        let dt = frame_time * self.acceleration;
+        self.steps_per_frame = 0;
+        self.falling_behind = false;
+        if dt <= 0.0 {
+            return;
+        }
+        if self.elapsed == 0.0 {
+            // record the initial state before the first step
+            self.diagnostics.update(world, self.elapsed);
+        }
+        self.advance(world, dt);
+
+        self.observe(world);
+        self.diagnostics.update(world, self.elapsed);
+        if self.diagnostics.exceeded() && !self.drift_alarm {
+            // pause once and let the user decide whether to go on
+            self.drift_alarm = true;
+            self.toggle_pause();
+        }
+    }
+
+    /// update the orbit of the tracked body and spheres of influence after steps
+    ///
+    /// update calls it once per frame. callers making steps on their own decide how often.
+    /// spheres of influence are only updated when they are enabled or shown.
+    pub fn observe(&mut self, world: &mut World) {
+        self.orbits.update(world);
+        self.spheres.update(world, self.elapsed);
+    }
+
+    /// split frame time into physics steps according to the timestep
+    fn advance(&mut self, world: &mut World, dt: f64) {
+        match self.timestep {
+            Timestep::Frame => self.step(world, dt),
+            Timestep::Fixed {
+                max_step,
+                max_steps,
+            } => {
+                self.accumulator += dt;
+                // adaptive integrators make sub-steps, only fixed steps count against the cap
+                let mut steps = 0;
+                while self.accumulator >= max_step && steps < max_steps {
+                    self.step(world, max_step);
+                    self.accumulator -= max_step;
+                    steps += 1;
+                }
+                if self.accumulator >= max_step {
+                    // drop the time we can't catch up with instead of piling it up
+                    self.falling_behind = true;
+                    self.accumulator %= max_step;
+                }
+            }
+        }
+    }
+
+    /// advance the universe by dt simulated seconds
+    pub fn step(&mut self, world: &mut World, dt: f64) {
+        self.steps_per_frame += match self.propagation {
+            Propagation::NBody => self.motion.update(world, dt, &mut self.dynamics),
+            Propagation::PatchedConics => {
+                self.conics.update(world, dt);
+                self.motion.invalidate_accelerations();
+                1
+            }
+        };
+        if self.collisions.update(world) {
+            self.motion.invalidate_accelerations();
+        }
+        self.elapsed += dt;
+        self.encounters.update(world, self.elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::force::ForceComponent;
+    use crate::physics::gravity::{MassComponent, G};
+    use crate::physics::motion::Motion;
+    use hecs::Entity;
+
+    /// universe running in real time with a single body drifting along the x axis
+    fn drifting_body(max_step: f64, max_steps: usize) -> (Universe, World, Entity) {
+        let mut world = World::new();
+        let body = world.spawn((Motion::new_position_velocity([0.0, 0.0], [1.0, 0.0]),));
+        let mut universe = Universe::new();
+        universe.change_acceleration(1.0);
+        universe.set_timestep(Timestep::Fixed {
+            max_step,
+            max_steps,
+        });
+        (universe, world, body)
+    }
+
+    fn position(world: &World, body: Entity) -> f64 {
+        world.get::<&Motion>(body).unwrap().position[0]
+    }
+
+    #[test]
+    fn fixed_steps_accumulate_frame_time() {
+        let (mut universe, mut world, body) = drifting_body(1.0, 10);
+        universe.update(2.5, &mut world);
+        assert_eq!(universe.steps_per_frame(), 2);
+        assert_eq!(universe.elapsed(), 2.0);
+        assert_eq!(position(&world, body), 2.0);
+
+        // the left over half a step completes with the next frame
+        universe.update(0.5, &mut world);
+        assert_eq!(universe.steps_per_frame(), 1);
+        assert_eq!(universe.elapsed(), 3.0);
+        assert!(!universe.falling_behind());
+    }
+
+    #[test]
+    fn steps_per_frame_are_capped() {
+        let (mut universe, mut world, _body) = drifting_body(1.0, 3);
+        universe.update(10.5, &mut world);
+        assert_eq!(universe.steps_per_frame(), 3);
+        assert_eq!(universe.elapsed(), 3.0);
+        assert!(universe.falling_behind());
+
+        // time that couldn't be simulated is dropped instead of piling up
+        universe.update(1.0, &mut world);
+        assert_eq!(universe.steps_per_frame(), 1);
+        assert_eq!(universe.elapsed(), 4.0);
+        assert!(!universe.falling_behind());
+    }
+
+    #[test]
+    fn adaptive_sub_steps_do_not_count_against_the_cap() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let speed = f64::sqrt(G * 1.0e30 / 1.0e11);
+        world.spawn((
+            MassComponent::new(1.0e20),
+            ForceComponent::zero(),
+            Motion::new_position_velocity([1.0e11, 0.0], [0.0, speed]),
+        ));
+        let mut universe = Universe::new();
+        universe.change_acceleration(1.0);
+        universe.set_integrator(Integrator::DormandPrince { tolerance: 1.0e-9 });
+        universe.set_timestep(Timestep::Fixed {
+            max_step: 1.0e6,
+            max_steps: 2,
+        });
+        universe.update(2.0e6, &mut world);
+        assert!(universe.steps_per_frame() > 2);
+        assert_eq!(universe.elapsed(), 2.0e6);
+        assert!(!universe.falling_behind());
+    }
+
+    #[test]
+    fn invalid_timesteps() {
+        assert!(Timestep::Frame.is_valid());
+        let fixed = |max_step, max_steps| Timestep::Fixed {
+            max_step,
+            max_steps,
+        };
+        assert!(fixed(60.0, 500).is_valid());
+        assert!(!fixed(0.0, 500).is_valid());
+        assert!(!fixed(-60.0, 500).is_valid());
+        assert!(!fixed(f64::NAN, 500).is_valid());
+        assert!(!fixed(60.0, 0).is_valid());
     }
 }
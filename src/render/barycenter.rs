@@ -0,0 +1,65 @@
+use crate::physics::gravity::center_of_mass;
+use crate::render::camera::Camera;
+use graphics::types::Color;
+use graphics::{Context, Ellipse, Line};
+use hecs::World;
+use opengl_graphics::GlGraphics;
+
+const BARYCENTER_COLOR: Color = [1.0, 0.4, 0.8, 0.8];
+/// radius of the marker in pixels
+const MARKER_RADIUS: f64 = 5.0;
+
+/// marks the center of mass of all bodies. hidden by default
+pub struct BarycenterOverlaySystem {
+    visible: bool,
+}
+
+impl Default for BarycenterOverlaySystem {
+    fn default() -> Self {
+        BarycenterOverlaySystem::new()
+    }
+}
+
+impl BarycenterOverlaySystem {
+    pub fn new() -> Self {
+        BarycenterOverlaySystem { visible: false }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn update(&self, world: &World, camera: &Camera, context: Context, gl: &mut GlGraphics) {
+        if !self.visible {
+            return;
+        }
+        let (position, _velocity) = match center_of_mass(world) {
+            Some(center) => center,
+            None => return,
+        };
+        let [x, y] = camera.project(position);
+        let (draw_state, transform) = (&context.draw_state, context.transform);
+        let bound = [
+            x - MARKER_RADIUS,
+            y - MARKER_RADIUS,
+            MARKER_RADIUS * 2.0,
+            MARKER_RADIUS * 2.0,
+        ];
+        Ellipse::new_border(BARYCENTER_COLOR, 0.5).draw(bound, draw_state, transform, gl);
+        let line = Line::new(BARYCENTER_COLOR, 0.5);
+        line.draw_from_to(
+            [x - MARKER_RADIUS, y],
+            [x + MARKER_RADIUS, y],
+            draw_state,
+            transform,
+            gl,
+        );
+        line.draw_from_to(
+            [x, y - MARKER_RADIUS],
+            [x, y + MARKER_RADIUS],
+            draw_state,
+            transform,
+            gl,
+        );
+    }
+}
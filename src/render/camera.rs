@@ -1,78 +1,214 @@
+use crate::core::tracking::TrackingComponent;
 use crate::physics::motion::{Motion, Position};
+use crate::render::frame::{FrameTransform, ReferenceFrame};
 use crate::render::render_box::RenderBoxComponent;
 use graphics::{Context, Transformed};
 use hecs::{Entity, World};
 use interpolation;
 use piston::input::RenderArgs;
-use std::collections::HashMap;
 
 pub enum TrackingMode {
     Fixed,
     Tracking(Entity),
 }
 
-pub struct TrackingComponent {}
-
-impl Default for TrackingComponent {
-    fn default() -> Self {
-        TrackingComponent::new()
-    }
-}
-
-impl TrackingComponent {
-    pub fn new() -> Self {
-        TrackingComponent {}
-    }
-}
+/// zoom change of a single mouse wheel notch
+const ZOOM_FACTOR: f64 = 1.25;
+/// frames it takes to reach a new zoom
+const ZOOM_STEPS: u32 = 16;
+/// zoom limits in pixels per meter
+const MIN_ZOOM: f64 = 1.0e-12;
+const MAX_ZOOM: f64 = 1.0e-2;
+/// share of the screen taken by bodies fitted into the view
+const FIT_MARGIN: f64 = 0.9;
 
 pub struct Camera {
     zoom: Zoom,
-    zoom_step: f64,
+    /// screen point that stays in place while zooming the free camera
+    zoom_anchor: Option<Position>,
+    /// position in the reference frame shown in the middle of the screen
+    pub center: Position,
+    /// translation from projected world coordinates to the screen
     pub focus: Position,
     pub tracking: TrackingMode,
+    transition: Transition,
+    /// position the free camera glides to
+    destination: Option<Position>,
+    window_size: Position,
+    frame: ReferenceFrame,
+    /// frame the transform belongs to
+    applied_frame: ReferenceFrame,
+    /// world to frame coordinates at the current frame
+    transform: FrameTransform,
 }
 
 impl Camera {
     #[allow(dead_code)]
     pub fn tracking(zoom: f64, entity: Entity) -> Self {
         Camera {
-            zoom: Zoom::new(zoom),
-            zoom_step: zoom / 16.0,
-            focus: [0.0, 0.0],
             tracking: TrackingMode::Tracking(entity),
+            ..Camera::fixed(zoom)
         }
     }
 
     pub fn fixed(zoom: f64) -> Self {
         Camera {
             zoom: Zoom::new(zoom),
-            zoom_step: zoom / 16.0,
+            zoom_anchor: None,
+            center: [0.0, 0.0],
             focus: [0.0, 0.0],
             tracking: TrackingMode::Fixed,
+            transition: Transition::new(),
+            destination: None,
+            window_size: [0.0, 0.0],
+            frame: ReferenceFrame::Inertial,
+            applied_frame: ReferenceFrame::Inertial,
+            transform: FrameTransform::identity(),
         }
     }
 
+    /// world position projected through the reference frame of the camera
     pub fn project(&self, coords: Position) -> Position {
+        self.project_local(self.to_frame(coords))
+    }
+
+    /// position in the reference frame projected without further transformation
+    pub fn project_local(&self, coords: Position) -> Position {
         vecmath::vec2_scale(coords, self.zoom.zoom)
     }
 
-    pub fn zoom_in(&mut self) {
-        self.zoom.change_zoom_relative(self.zoom_step, 16);
+    /// frame coordinates of the world position
+    pub fn to_frame(&self, position: Position) -> Position {
+        self.transform.apply(position)
+    }
+
+    pub fn frame(&self) -> ReferenceFrame {
+        self.frame
+    }
+
+    pub fn transform(&self) -> FrameTransform {
+        self.transform
+    }
+
+    /// draw the scene in another frame. the view stays on the same place in space
+    pub fn set_frame(&mut self, frame: ReferenceFrame) {
+        self.frame = frame;
+    }
+
+    /// length in meters on the screen
+    pub fn project_length(&self, length: f64) -> f64 {
+        length * self.zoom.zoom
+    }
+
+    /// zoom in towards the cursor. a tracking camera zooms towards the tracked body
+    pub fn zoom_in(&mut self, cursor: Position) {
+        self.zoom.change_zoom_by(ZOOM_FACTOR, ZOOM_STEPS);
+        self.zoom_anchor = Some(cursor);
     }
 
-    pub fn zoom_out(&mut self) {
-        self.zoom.change_zoom_relative(-self.zoom_step, 16);
+    /// zoom out from the cursor. a tracking camera zooms out from the tracked body
+    pub fn zoom_out(&mut self, cursor: Position) {
+        self.zoom.change_zoom_by(1.0 / ZOOM_FACTOR, ZOOM_STEPS);
+        self.zoom_anchor = Some(cursor);
+    }
+
+    /// glide to the middle of the area and zoom to fit it into the screen
+    pub fn fit(&mut self, min: Position, max: Position) {
+        let size = vecmath::vec2_sub(max, min);
+        let center = vecmath::vec2_add(min, vecmath::vec2_scale(size, 0.5));
+        self.transition.start(self.center, TRANSITION_STEPS);
+        self.destination = Some(center);
+        self.zoom_anchor = None;
+        let zoom = f64::min(self.window_size[0] / size[0], self.window_size[1] / size[1]);
+        if zoom.is_finite() {
+            self.zoom.change_zoom_clamped(zoom * FIT_MARGIN, ZOOM_STEPS);
+        }
+    }
+
+    /// zoom to fit a circle of the radius around the center of the view
+    pub fn fit_radius(&mut self, radius: f64) {
+        self.zoom_anchor = None;
+        let zoom = f64::min(self.window_size[0], self.window_size[1]) / (2.0 * radius);
+        if zoom.is_finite() {
+            self.zoom.change_zoom_clamped(zoom * FIT_MARGIN, ZOOM_STEPS);
+        }
+    }
+
+    /// position in the reference frame at the point of the screen
+    pub fn unproject(&self, point: Position) -> Position {
+        let screen_center = vecmath::vec2_scale(self.window_size, 0.5);
+        let offset = vecmath::vec2_sub(point, screen_center);
+        vecmath::vec2_add(
+            self.center,
+            vecmath::vec2_scale(offset, 1.0 / self.zoom.zoom),
+        )
+    }
+
+    /// move the view by the offset in screen pixels
+    pub fn pan(&mut self, offset: Position) {
+        let world_offset = vecmath::vec2_scale(offset, 1.0 / self.zoom.zoom);
+        self.center = vecmath::vec2_sub(self.center, world_offset);
+        self.destination = None;
     }
 
     fn update_zoom(&mut self) {
+        let anchor = match self.tracking {
+            TrackingMode::Fixed => self.zoom_anchor,
+            TrackingMode::Tracking(_) => None,
+        };
+        let anchored = anchor.map(|anchor| (anchor, self.unproject(anchor)));
         self.zoom.update();
+        if let Some((anchor, anchored)) = anchored {
+            // keep the world point under the anchor in place
+            let moved = vecmath::vec2_sub(self.unproject(anchor), self.center);
+            self.center = vecmath::vec2_sub(anchored, moved);
+        }
+        if self.zoom.steps_left == 0 {
+            self.zoom_anchor = None;
+        }
     }
 
+    /// start following the entity. the view glides from the current center to the entity
     fn track(&mut self, entity: Entity) {
-        self.tracking = TrackingMode::Tracking(entity);
+        match self.tracking {
+            TrackingMode::Tracking(tracked) if tracked == entity => {}
+            _ => {
+                self.transition.start(self.center, TRANSITION_STEPS);
+                self.tracking = TrackingMode::Tracking(entity);
+                self.destination = None;
+            }
+        }
+    }
+
+    /// update the transform to current positions of bodies
+    ///
+    /// when the frame was switched the view stays on the same place in space. falls back to
+    /// the inertial frame when bodies of the frame are gone.
+    fn update_frame(&mut self, world: &World) {
+        let transform = self.frame.transform(world).unwrap_or_else(|| {
+            self.frame = ReferenceFrame::Inertial;
+            FrameTransform::identity()
+        });
+        if self.frame != self.applied_frame {
+            let previous = self.transform;
+            let moved = |position: Position| transform.apply(previous.invert(position));
+            self.center = moved(self.center);
+            self.transition.start = moved(self.transition.start);
+            self.destination = self.destination.map(moved);
+            self.applied_frame = self.frame;
+        }
+        self.transform = transform;
+    }
+
+    /// move the center towards the position of the tracked entity
+    fn follow(&mut self, target: Position) {
+        self.center = self.transition.update(target);
     }
 }
 
+/// frames it takes to move the view to a newly tracked body
+const TRANSITION_STEPS: u32 = 32;
+
 pub struct CameraSystem {
     pub camera: Camera,
 }
@@ -83,33 +219,78 @@ impl CameraSystem {
     }
 
     pub fn update(&mut self, context: Context, world: &mut World, args: RenderArgs) -> Context {
+        self.camera.window_size = args.window_size;
         self.camera.update_zoom();
-        let mut projected_positions_by_entity: HashMap<Entity, Position> = HashMap::new();
-        for (id, (motion, render_box)) in &mut world.query::<(&Motion, &mut RenderBoxComponent)>() {
-            let projected_position = self.camera.project(motion.position);
-            render_box.move_to(projected_position);
-            projected_positions_by_entity.insert(id, projected_position);
+        self.camera.update_frame(world);
+        match world.query::<&TrackingComponent>().iter().next() {
+            Some((id, _tracking)) => self.camera.track(id),
+            None => self.camera.tracking = TrackingMode::Fixed,
         }
-
-        for (id, (_tracking,)) in &mut world.query::<(&TrackingComponent,)>() {
-            self.camera.track(id);
+        if let TrackingMode::Tracking(entity) = self.camera.tracking {
+            let target = world
+                .get::<&Motion>(entity)
+                .map(|motion| self.camera.to_frame(motion.position));
+            match target {
+                Ok(target) => self.camera.follow(target),
+                // tracked body is gone, keep looking at the same place
+                Err(_) => self.camera.tracking = TrackingMode::Fixed,
+            }
+        } else if let Some(destination) = self.camera.destination {
+            self.camera.follow(destination);
+            if self.camera.center == destination {
+                self.camera.destination = None;
+            }
         }
 
         let screen_center = vecmath::vec2_scale(args.window_size, 0.5);
-        match self.camera.tracking {
-            TrackingMode::Fixed => {
-                self.camera.focus = screen_center;
-            }
-            TrackingMode::Tracking(e) => {
-                let entity_position = projected_positions_by_entity[&e];
-                let focus = vecmath::vec2_sub(screen_center, entity_position);
-                self.camera.focus = focus;
-            }
+        self.camera.focus =
+            vecmath::vec2_sub(screen_center, self.camera.project_local(self.camera.center));
+        for (_id, (motion, render_box)) in &mut world.query::<(&Motion, &mut RenderBoxComponent)>()
+        {
+            render_box.move_to(self.camera.project(motion.position));
         }
         context.trans_pos(self.camera.focus)
     }
 }
 
+/// gradual move of the view towards a possibly moving target
+struct Transition {
+    start: Position,
+    steps: u32,
+    steps_left: u32,
+}
+
+impl Transition {
+    fn new() -> Self {
+        Transition {
+            start: [0.0, 0.0],
+            steps: 0,
+            steps_left: 0,
+        }
+    }
+
+    fn start(&mut self, from: Position, steps: u32) {
+        self.start = from;
+        self.steps = steps;
+        self.steps_left = steps;
+    }
+
+    /// position between the start and the target. reaches the target once steps are over
+    fn update(&mut self, target: Position) -> Position {
+        if self.steps_left == 0 {
+            return target;
+        }
+        self.steps_left -= 1;
+        let ip = (self.steps - self.steps_left) as f64 / self.steps as f64;
+        // ease in and out so the view doesn't jerk at both ends
+        let ip = ip * ip * (3.0 - 2.0 * ip);
+        [
+            interpolation::lerp(&self.start[0], &target[0], &ip),
+            interpolation::lerp(&self.start[1], &target[1], &ip),
+        ]
+    }
+}
+
 struct Zoom {
     zoom: f64,
     start_zoom: f64,
@@ -139,20 +320,28 @@ impl Zoom {
         self.steps_left = steps;
     }
 
-    fn change_zoom_relative(&mut self, change_by: f64, steps: u32) {
-        self.change_zoom(self.zoom + change_by, steps);
+    /// multiply the zoom, accumulating changes that are still in progress
+    fn change_zoom_by(&mut self, factor: f64, steps: u32) {
+        self.change_zoom_clamped(self.target_zoom * factor, steps);
+    }
+
+    /// change zoom within the limits
+    fn change_zoom_clamped(&mut self, new_zoom: f64, steps: u32) {
+        self.change_zoom(new_zoom.clamp(MIN_ZOOM, MAX_ZOOM), steps);
     }
 
+    /// move the zoom towards the target by the same factor every step
     fn update(&mut self) {
         if self.steps_left == 0 {
             return;
         }
         self.steps_left -= 1;
-        let start_zoom = self.start_zoom;
-        let target_zoom = self.target_zoom;
         let ip = (self.steps - self.steps_left) as f64 / self.steps as f64;
-        let zoom = interpolation::lerp::<f64>(&start_zoom, &target_zoom, &ip);
-        self.zoom = zoom;
+        self.zoom = if self.steps_left == 0 {
+            self.target_zoom
+        } else {
+            self.start_zoom * (self.target_zoom / self.start_zoom).powf(ip)
+        };
     }
 }
 
@@ -163,29 +352,137 @@ mod tests {
     #[test]
     fn change_zoom() {
         let mut zoom = Zoom::new(512.0);
-        zoom.change_zoom(1024.0, 2);
-        zoom.update();
-        assert_eq!(zoom.zoom, 768.0, "first step of zoom must close by half");
+        zoom.change_zoom(2048.0, 2);
         zoom.update();
         assert_eq!(
             zoom.zoom, 1024.0,
+            "first step of zoom must reach the geometric mean"
+        );
+        zoom.update();
+        assert_eq!(
+            zoom.zoom, 2048.0,
             "second step of zoom must reach the target"
         );
         zoom.update();
-        assert_eq!(zoom.zoom, 1024.0, "other steps must not change zoom at all");
+        assert_eq!(zoom.zoom, 2048.0, "other steps must not change zoom at all");
+    }
+
+    #[test]
+    fn large_zoom_changes_are_geometric() {
+        let mut zoom = Zoom::new(1.0e-9);
+        zoom.change_zoom(1.0e-3, 16);
+        for _ in 0..8 {
+            zoom.update();
+        }
+        assert!((zoom.zoom / 1.0e-6 - 1.0).abs() < 1.0e-12, "{}", zoom.zoom);
+        for _ in 0..8 {
+            zoom.update();
+        }
+        assert_eq!(zoom.zoom, 1.0e-3);
+    }
+
+    #[test]
+    fn transition_reaches_moving_target() {
+        let mut transition = Transition::new();
+        transition.start([0.0, 0.0], 2);
+        assert_eq!(transition.update([8.0, 4.0]), [4.0, 2.0]);
+        assert_eq!(transition.update([10.0, 4.0]), [10.0, 4.0]);
+        assert_eq!(transition.update([12.0, 4.0]), [12.0, 4.0]);
+    }
+
+    #[test]
+    fn pan_moves_center_against_the_drag() {
+        let mut camera = Camera::fixed(0.5);
+        camera.pan([10.0, -4.0]);
+        assert_eq!(camera.center, [-20.0, 8.0]);
     }
 
     #[test]
     fn zoom_in() {
-        let mut zoom = Zoom::new(512.0);
-        zoom.change_zoom_relative(256.0, 4);
-        assert_eq!(zoom.target_zoom, 768.0);
+        let mut zoom = Zoom::new(1.0e-6);
+        zoom.change_zoom_by(2.0, 4);
+        zoom.change_zoom_by(2.0, 4);
+        assert_eq!(zoom.target_zoom, 4.0e-6);
     }
 
     #[test]
     fn zoom_out() {
-        let mut zoom = Zoom::new(512.0);
-        zoom.change_zoom_relative(-256.0, 4);
-        assert_eq!(zoom.target_zoom, 256.0);
+        let mut zoom = Zoom::new(1.0e-6);
+        zoom.change_zoom_by(0.5, 4);
+        assert_eq!(zoom.target_zoom, 0.5e-6);
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut zoom = Zoom::new(MAX_ZOOM);
+        zoom.change_zoom_by(2.0, 4);
+        assert_eq!(zoom.target_zoom, MAX_ZOOM);
+        zoom.change_zoom_clamped(0.0, 4);
+        assert_eq!(zoom.target_zoom, MIN_ZOOM);
+    }
+
+    #[test]
+    fn switching_frame_keeps_the_view_in_place() {
+        let mut world = World::new();
+        let body = world.spawn((Motion::position([100.0, 50.0]),));
+        let mut camera = Camera::fixed(1.0);
+        camera.center = [30.0, 20.0];
+        camera.set_frame(ReferenceFrame::Body(body));
+        camera.update_frame(&world);
+        assert_eq!(camera.center, [-70.0, -30.0]);
+        assert_eq!(camera.project([100.0, 50.0]), [0.0, 0.0]);
+
+        // frame falls back to inertial without its body
+        world.despawn(body).unwrap();
+        camera.update_frame(&world);
+        assert_eq!(camera.frame(), ReferenceFrame::Inertial);
+        assert_eq!(camera.center, [30.0, 20.0]);
+    }
+
+    #[test]
+    fn zoom_towards_cursor() {
+        let mut camera = Camera::fixed(1.0e-3);
+        camera.window_size = [800.0, 600.0];
+        let cursor = [700.0, 100.0];
+        let anchored = camera.unproject(cursor);
+        camera.zoom_in(cursor);
+        for _ in 0..ZOOM_STEPS {
+            camera.update_zoom();
+        }
+        let error = vecmath::vec2_len(vecmath::vec2_sub(camera.unproject(cursor), anchored));
+        assert!(error < 1.0e-6, "cursor moved by {} m", error);
+        assert!((camera.project_length(1.0) - 1.25e-3).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn tracked_body_is_in_the_middle_of_the_screen() {
+        use crate::physics::gravity::MassComponent;
+
+        let mut world = World::new();
+        let primary = world.spawn((Motion::position([1.0e6, 0.0]), MassComponent::new(3.0)));
+        let secondary = world.spawn((
+            Motion::position([5.0e6, 3.0e6]),
+            MassComponent::new(1.0),
+            TrackingComponent::new(),
+        ));
+        let args = RenderArgs {
+            ext_dt: 0.0,
+            window_size: [800.0, 600.0],
+            draw_size: [800, 600],
+        };
+        for frame in [
+            ReferenceFrame::Body(secondary),
+            ReferenceFrame::CoRotating { primary, secondary },
+        ] {
+            let mut system = CameraSystem::new(Camera::fixed(1.0e-4));
+            system.camera.set_frame(frame);
+            for _ in 0..=TRANSITION_STEPS {
+                system.update(Context::new(), &mut world, args);
+            }
+            let camera = &system.camera;
+            let screen = vecmath::vec2_add(camera.focus, camera.project([5.0e6, 3.0e6]));
+            let error = vecmath::vec2_len(vecmath::vec2_sub(screen, [400.0, 300.0]));
+            assert!(error < 1.0e-6, "{:?} is off by {} px", frame, error);
+        }
     }
 }
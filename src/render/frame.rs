@@ -0,0 +1,136 @@
+use crate::core::name::name_of;
+use crate::physics::gravity::{center_of_mass, MassComponent};
+use crate::physics::motion::{Motion, Position};
+use hecs::{Entity, World};
+
+/// coordinate system the scene is drawn in
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ReferenceFrame {
+    /// world coordinates
+    #[default]
+    Inertial,
+    /// origin at the center of mass of all bodies
+    Barycentric,
+    /// origin at the body
+    Body(Entity),
+    /// origin at the barycenter of the pair, the secondary always lies to the right of the primary
+    ///
+    /// kerbin and the mun form a synodic frame where bodies near lagrange points stand still.
+    CoRotating { primary: Entity, secondary: Entity },
+}
+
+impl ReferenceFrame {
+    /// transform from world coordinates for the current positions of bodies
+    ///
+    /// none when a body of the frame is gone.
+    pub fn transform(&self, world: &World) -> Option<FrameTransform> {
+        let position = |entity: Entity| world.get::<&Motion>(entity).ok().map(|m| m.position);
+        match *self {
+            ReferenceFrame::Inertial => Some(FrameTransform::identity()),
+            ReferenceFrame::Barycentric => Some(FrameTransform {
+                origin: center_of_mass(world).map_or([0.0, 0.0], |(position, _)| position),
+                angle: 0.0,
+            }),
+            ReferenceFrame::Body(body) => Some(FrameTransform {
+                origin: position(body)?,
+                angle: 0.0,
+            }),
+            ReferenceFrame::CoRotating { primary, secondary } => {
+                let mass =
+                    |entity: Entity| world.get::<&MassComponent>(entity).map_or(0.0, |m| m.mass);
+                let (p, s) = (position(primary)?, position(secondary)?);
+                let (m1, m2) = (mass(primary), mass(secondary));
+                let share = if m1 + m2 > 0.0 { m2 / (m1 + m2) } else { 0.0 };
+                let axis = vecmath::vec2_sub(s, p);
+                Some(FrameTransform {
+                    origin: vecmath::vec2_add(p, vecmath::vec2_scale(axis, share)),
+                    angle: axis[1].atan2(axis[0]),
+                })
+            }
+        }
+    }
+
+    pub fn describe(&self, world: &World) -> String {
+        match *self {
+            ReferenceFrame::Inertial => String::from("inertial"),
+            ReferenceFrame::Barycentric => String::from("barycentric"),
+            ReferenceFrame::Body(body) => format!("centered on {}", name_of(world, body)),
+            ReferenceFrame::CoRotating { primary, secondary } => format!(
+                "co-rotating {}-{}",
+                name_of(world, primary),
+                name_of(world, secondary)
+            ),
+        }
+    }
+}
+
+/// translation and rotation from world coordinates to the coordinates of a frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameTransform {
+    /// world position of the origin of the frame
+    pub origin: Position,
+    /// radians the frame is turned counterclockwise
+    pub angle: f64,
+}
+
+impl FrameTransform {
+    pub fn identity() -> Self {
+        FrameTransform {
+            origin: [0.0, 0.0],
+            angle: 0.0,
+        }
+    }
+
+    /// frame coordinates of the world position
+    pub fn apply(&self, position: Position) -> Position {
+        rotate(vecmath::vec2_sub(position, self.origin), -self.angle)
+    }
+
+    /// world position of the frame coordinates
+    pub fn invert(&self, position: Position) -> Position {
+        vecmath::vec2_add(rotate(position, self.angle), self.origin)
+    }
+}
+
+fn rotate(v: Position, angle: f64) -> Position {
+    let (sin, cos) = angle.sin_cos();
+    [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(lhs: Position, rhs: Position) -> bool {
+        vecmath::vec2_len(vecmath::vec2_sub(lhs, rhs)) < 1.0e-9
+    }
+
+    #[test]
+    fn co_rotating_frame_keeps_the_pair_on_the_axis() {
+        let mut world = World::new();
+        let primary = world.spawn((MassComponent::new(3.0), Motion::position([0.0, 4.0])));
+        let secondary = world.spawn((MassComponent::new(1.0), Motion::position([0.0, 8.0])));
+        let frame = ReferenceFrame::CoRotating { primary, secondary };
+        let transform = frame.transform(&world).unwrap();
+
+        assert!(close(transform.origin, [0.0, 5.0]));
+        assert!(close(transform.apply([0.0, 4.0]), [-1.0, 0.0]));
+        assert!(close(transform.apply([0.0, 8.0]), [3.0, 0.0]));
+        assert!(close(
+            transform.invert(transform.apply([7.0, -2.0])),
+            [7.0, -2.0]
+        ));
+
+        world.despawn(secondary).unwrap();
+        assert_eq!(frame.transform(&world), None);
+    }
+
+    #[test]
+    fn barycentric_frame() {
+        let mut world = World::new();
+        world.spawn((MassComponent::new(1.0), Motion::position([2.0, 0.0])));
+        world.spawn((MassComponent::new(1.0), Motion::position([4.0, 2.0])));
+        let transform = ReferenceFrame::Barycentric.transform(&world).unwrap();
+        assert!(close(transform.apply([3.0, 1.0]), [0.0, 0.0]));
+    }
+}
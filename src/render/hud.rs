@@ -0,0 +1,252 @@
+use crate::core::name::NameComponent;
+use crate::core::tracking::TrackingComponent;
+use crate::physics::gravity::MassComponent;
+use crate::physics::orbit::OrbitComponent;
+use crate::physics::universe::Universe;
+use crate::render::camera::Camera;
+use graphics::types::{Color, FontSize};
+use graphics::{Context, Transformed};
+use hecs::World;
+use opengl_graphics::{GlGraphics, GlyphCache};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+const FONT_SIZE: FontSize = 16;
+const MARGIN: f64 = 16.0;
+const LINE_HEIGHT: f64 = FONT_SIZE as f64 * 1.5;
+const TEXT_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+const WARNING_COLOR: Color = [1.0, 0.3, 0.3, 1.0];
+/// weight of the latest frame in the smoothed frame rate
+const FPS_SMOOTHING: f64 = 0.1;
+/// width reserved for the orbit panel at the right edge of the screen
+const ORBIT_PANEL_WIDTH: f64 = 320.0;
+/// latest events shown at the bottom of the screen
+const EVENT_LINES: usize = 5;
+
+/// renders simulation status on top of the scene in screen coordinates
+pub struct HudSystem {
+    last_frame: Option<Instant>,
+    fps: f64,
+    events: VecDeque<String>,
+}
+
+impl Default for HudSystem {
+    fn default() -> Self {
+        HudSystem::new()
+    }
+}
+
+impl HudSystem {
+    pub fn new() -> Self {
+        HudSystem {
+            last_frame: None,
+            fps: 0.0,
+            events: VecDeque::with_capacity(EVENT_LINES),
+        }
+    }
+
+    /// add the message to the event log, dropping the oldest one when the log is full
+    pub fn log(&mut self, message: String) {
+        if self.events.len() == EVENT_LINES {
+            self.events.pop_front();
+        }
+        self.events.push_back(message);
+    }
+
+    pub fn update(
+        &mut self,
+        world: &World,
+        universe: &Universe,
+        camera: &Camera,
+        glyphs: &mut GlyphCache,
+        context: Context,
+        gl: &mut GlGraphics,
+    ) {
+        let drift = universe.diagnostics().drift();
+        let drift_color = if universe.drift_alarm() {
+            WARNING_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        self.measure_fps();
+        let warp = if universe.paused() {
+            String::from("paused")
+        } else {
+            format!("warp: x{}", universe.acceleration())
+        };
+        let bodies = world.query::<&MassComponent>().iter().count();
+        let mut lines = vec![
+            (
+                format!("time: {}", format_duration(universe.elapsed())),
+                TEXT_COLOR,
+            ),
+            (warp, TEXT_COLOR),
+            (format!("bodies: {}", bodies), TEXT_COLOR),
+            (
+                format!("frame: {}", camera.frame().describe(world)),
+                TEXT_COLOR,
+            ),
+            (format!("fps: {:.0}", self.fps), TEXT_COLOR),
+            (
+                format!("steps per update: {}", universe.steps_per_frame()),
+                TEXT_COLOR,
+            ),
+            (format!("step: {:.2} s", universe.step_size()), TEXT_COLOR),
+        ];
+        if universe.diagnostics().active() {
+            lines.extend([
+                (format!("energy drift: {:.2e}", drift.energy), drift_color),
+                (
+                    format!("momentum drift: {:.2e}", drift.momentum),
+                    drift_color,
+                ),
+                (
+                    format!("angular momentum drift: {:.2e}", drift.angular_momentum),
+                    drift_color,
+                ),
+            ]);
+        }
+        if universe.drift_alarm() {
+            lines.push((String::from("drift threshold exceeded"), WARNING_COLOR));
+        }
+        if universe.falling_behind() {
+            lines.push((String::from("falling behind"), WARNING_COLOR));
+        }
+
+        draw_lines(&lines, [MARGIN, MARGIN], glyphs, context, gl);
+
+        let orbit_lines = orbit_panel(world);
+        let [width, height] = context.get_view_size();
+        draw_lines(
+            &orbit_lines,
+            [width - ORBIT_PANEL_WIDTH, MARGIN],
+            glyphs,
+            context,
+            gl,
+        );
+
+        let event_lines: Vec<(String, Color)> = self
+            .events
+            .iter()
+            .map(|event| (event.clone(), TEXT_COLOR))
+            .collect();
+        let top = height - MARGIN - FONT_SIZE as f64 - EVENT_LINES as f64 * LINE_HEIGHT;
+        draw_lines(&event_lines, [MARGIN, top], glyphs, context, gl);
+    }
+
+    fn measure_fps(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let frame_time = now.duration_since(last_frame).as_secs_f64();
+            if frame_time > 0.0 {
+                let fps = 1.0 / frame_time;
+                self.fps = if self.fps > 0.0 {
+                    self.fps + FPS_SMOOTHING * (fps - self.fps)
+                } else {
+                    fps
+                };
+            }
+        }
+        self.last_frame = Some(now);
+    }
+}
+
+/// orbital elements of the tracked body
+fn orbit_panel(world: &World) -> Vec<(String, Color)> {
+    let mut query = world.query::<(&TrackingComponent, &NameComponent, &OrbitComponent)>();
+    let (_id, (_tracking, name, orbit)) = match query.iter().next() {
+        Some(tracked) => tracked,
+        None => return vec![],
+    };
+    let attractor = world
+        .get::<&NameComponent>(orbit.attractor)
+        .map_or(String::from("?"), |name| name.name.clone());
+    let elements = &orbit.elements;
+    let line = |text: String| (text, TEXT_COLOR);
+    vec![
+        line(format!("{} around {}", name.name, attractor)),
+        line(format!(
+            "apoapsis: {}",
+            elements
+                .apoapsis()
+                .map_or(String::from("escape"), format_distance)
+        )),
+        line(format!(
+            "periapsis: {}",
+            format_distance(elements.periapsis())
+        )),
+        line(format!("eccentricity: {:.4}", elements.eccentricity)),
+        line(format!(
+            "period: {}",
+            orbit.period().map_or(String::from("-"), format_duration)
+        )),
+    ]
+}
+
+fn format_distance(meters: f64) -> String {
+    format!("{:.0} km", meters / 1000.0)
+}
+
+/// duration in days, hours, minutes and seconds
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let (days, hours) = (total / 86400, total % 86400 / 3600);
+    let (minutes, seconds) = (total % 3600 / 60, total % 60);
+    if days > 0 {
+        format!("{}d {:02}h {:02}m {:02}s", days, hours, minutes, seconds)
+    } else {
+        format!("{:02}h {:02}m {:02}s", hours, minutes, seconds)
+    }
+}
+
+/// lines of text starting at the top left corner
+fn draw_lines(
+    lines: &[(String, Color)],
+    [left, top]: [f64; 2],
+    glyphs: &mut GlyphCache,
+    context: Context,
+    gl: &mut GlGraphics,
+) {
+    for (i, (line, color)) in lines.iter().enumerate() {
+        let position = [left, top + FONT_SIZE as f64 + i as f64 * LINE_HEIGHT];
+        graphics::text(
+            *color,
+            FONT_SIZE,
+            line,
+            glyphs,
+            context.trans_pos(position).transform,
+            gl,
+        )
+        .expect("can't render the hud");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_log_keeps_latest_messages() {
+        let mut hud = HudSystem::new();
+        for i in 0..EVENT_LINES + 2 {
+            hud.log(format!("event {}", i));
+        }
+        assert_eq!(hud.events.len(), EVENT_LINES);
+        assert_eq!(hud.events.front().unwrap(), "event 2");
+        assert_eq!(
+            hud.events.back().unwrap(),
+            &format!("event {}", EVENT_LINES + 1)
+        );
+    }
+
+    #[test]
+    fn format_duration_boundaries() {
+        assert_eq!(format_duration(59.4), "00h 00m 59s");
+        assert_eq!(format_duration(60.0), "00h 01m 00s");
+        assert_eq!(format_duration(3599.0), "00h 59m 59s");
+        assert_eq!(format_duration(3600.0), "01h 00m 00s");
+        assert_eq!(format_duration(86399.0), "23h 59m 59s");
+        assert_eq!(format_duration(86400.0), "1d 00h 00m 00s");
+        assert_eq!(format_duration(90061.0), "1d 01h 01m 01s");
+    }
+}
@@ -0,0 +1,90 @@
+use crate::physics::lagrange::{pair_lagrange_points, LagrangePoint};
+use crate::render::camera::Camera;
+use graphics::types::{Color, FontSize};
+use graphics::{Context, Line, Transformed};
+use hecs::{Entity, World};
+use opengl_graphics::{GlGraphics, GlyphCache};
+
+const MARKER_COLOR: Color = [1.0, 0.8, 0.3, 0.8];
+/// half of the size of a marker in pixels
+const MARKER_SIZE: f64 = 4.0;
+const FONT_SIZE: FontSize = 12;
+
+/// draws labeled lagrange points of a pair of bodies. hidden until a pair is chosen
+pub struct LagrangeOverlaySystem {
+    /// primary and secondary
+    pair: Option<(Entity, Entity)>,
+}
+
+impl Default for LagrangeOverlaySystem {
+    fn default() -> Self {
+        LagrangeOverlaySystem::new()
+    }
+}
+
+impl LagrangeOverlaySystem {
+    pub fn new() -> Self {
+        LagrangeOverlaySystem { pair: None }
+    }
+
+    pub fn pair(&self) -> Option<(Entity, Entity)> {
+        self.pair
+    }
+
+    pub fn set_pair(&mut self, pair: Option<(Entity, Entity)>) {
+        self.pair = pair;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &World,
+        camera: &Camera,
+        glyphs: &mut GlyphCache,
+        context: Context,
+        gl: &mut GlGraphics,
+    ) {
+        let (primary, secondary) = match self.pair {
+            Some(pair) => pair,
+            None => return,
+        };
+        let points = match pair_lagrange_points(world, primary, secondary) {
+            Some(points) => points,
+            None => {
+                // one of the bodies is gone
+                self.pair = None;
+                return;
+            }
+        };
+        let line = Line::new(MARKER_COLOR, 0.5);
+        for point in LagrangePoint::ALL {
+            let (position, _velocity) = points[point.index()];
+            let [x, y] = camera.project(position);
+            let (draw_state, transform) = (&context.draw_state, context.transform);
+            line.draw_from_to(
+                [x - MARKER_SIZE, y - MARKER_SIZE],
+                [x + MARKER_SIZE, y + MARKER_SIZE],
+                draw_state,
+                transform,
+                gl,
+            );
+            line.draw_from_to(
+                [x - MARKER_SIZE, y + MARKER_SIZE],
+                [x + MARKER_SIZE, y - MARKER_SIZE],
+                draw_state,
+                transform,
+                gl,
+            );
+            graphics::text(
+                MARKER_COLOR,
+                FONT_SIZE,
+                &point.to_string(),
+                glyphs,
+                context
+                    .trans(x + MARKER_SIZE * 2.0, y - MARKER_SIZE)
+                    .transform,
+                gl,
+            )
+            .expect("can't render the lagrange point");
+        }
+    }
+}
@@ -1,7 +1,14 @@
 pub mod background;
+pub mod barycenter;
 pub mod camera;
+pub mod frame;
+pub mod hud;
+pub mod lagrange;
 pub mod name;
+pub mod prediction;
 pub mod render_box;
 pub mod renderer;
+pub mod selection;
+pub mod soi;
 pub mod sprite;
-pub mod trace;
+pub mod trail;
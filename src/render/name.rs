@@ -1,3 +1,4 @@
+use crate::core::name::NameComponent;
 use crate::render::render_box::{RenderBoxComponent, RenderingPosition};
 use graphics::character::CharacterCache;
 use graphics::types::FontSize;
@@ -5,16 +6,6 @@ use graphics::{Context, Transformed};
 use hecs::World;
 use opengl_graphics::{GlGraphics, GlyphCache};
 
-pub struct NameComponent {
-    pub name: String,
-}
-
-impl NameComponent {
-    pub fn new(name: String) -> Self {
-        NameComponent { name }
-    }
-}
-
 pub struct NameSystem {}
 
 const FONT_SIZE: FontSize = 16;
@@ -0,0 +1,157 @@
+use crate::physics::motion::{Motion, Position};
+use crate::physics::orbit;
+use crate::render::camera::Camera;
+use graphics::types::Color;
+use graphics::{Context, Line};
+use hecs::{Entity, World};
+use opengl_graphics::GlGraphics;
+use serde::Deserialize;
+
+const PREDICTION_COLOR: Color = [0.5, 0.7, 1.0, 0.4];
+/// open orbits are drawn up to this multiple of the current distance to the attractor
+const OPEN_ORBIT_EXTENT: f64 = 3.0;
+
+/// settings of predicted orbit paths
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Prediction {
+    /// points of every path. zero disables predictions
+    pub points: usize,
+    /// frames between path updates
+    pub interval: usize,
+}
+
+impl Default for Prediction {
+    fn default() -> Self {
+        Prediction {
+            points: 128,
+            interval: 30,
+        }
+    }
+}
+
+/// predicted path of the body relative to its attractor
+pub struct PredictionComponent {
+    attractor: Entity,
+    points: Vec<Position>,
+}
+
+/// draws future paths of bodies as conics of their osculating orbits
+///
+/// paths are recalculated every few frames and follow their attractors in between.
+pub struct PredictionSystem {
+    settings: Prediction,
+    frames_since_update: usize,
+}
+
+impl Default for PredictionSystem {
+    fn default() -> Self {
+        PredictionSystem::new()
+    }
+}
+
+impl PredictionSystem {
+    pub fn new() -> Self {
+        PredictionSystem {
+            settings: Prediction::default(),
+            frames_since_update: usize::MAX,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: Prediction) {
+        self.settings = settings;
+        self.frames_since_update = usize::MAX;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        camera: &Camera,
+        context: Context,
+        gl: &mut GlGraphics,
+    ) {
+        if self.settings.points < 2 {
+            return;
+        }
+        if self.frames_since_update >= self.settings.interval {
+            self.predict(world);
+            self.frames_since_update = 0;
+        }
+        self.frames_since_update += 1;
+
+        let line = Line::new(PREDICTION_COLOR, 0.5);
+        for (_id, prediction) in &mut world.query::<&PredictionComponent>() {
+            let attractor = match world.get::<&Motion>(prediction.attractor) {
+                Ok(motion) => motion.position,
+                Err(_) => continue,
+            };
+            let project = |point: &Position| camera.project(vecmath::vec2_add(attractor, *point));
+            for segment in prediction.points.windows(2) {
+                line.draw_from_to(
+                    project(&segment[0]),
+                    project(&segment[1]),
+                    &context.draw_state,
+                    context.transform,
+                    gl,
+                );
+            }
+        }
+    }
+
+    fn predict(&self, world: &mut World) {
+        let mut predictions = vec![];
+        for (id, orbit) in orbit::orbits(world) {
+            let elements = &orbit.elements;
+            let max_distance = if elements.eccentricity < 1.0 {
+                f64::INFINITY
+            } else {
+                let distance = vecmath::vec2_len(elements.position_at(elements.true_anomaly));
+                OPEN_ORBIT_EXTENT * distance
+            };
+            let points = elements.path(self.settings.points, max_distance);
+            predictions.push((
+                id,
+                PredictionComponent {
+                    attractor: orbit.attractor,
+                    points,
+                },
+            ));
+        }
+
+        let stale: Vec<Entity> = world
+            .query::<&PredictionComponent>()
+            .iter()
+            .map(|(id, _)| id)
+            .collect();
+        for id in stale {
+            world.remove_one::<PredictionComponent>(id).unwrap();
+        }
+        for (id, prediction) in predictions {
+            world.insert_one(id, prediction).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::gravity::MassComponent;
+
+    #[test]
+    fn bodies_without_attractors_have_no_paths() {
+        let mut world = World::new();
+        let star = world.spawn((MassComponent::new(1.0e30), Motion::position([0.0, 0.0])));
+        let comet = world.spawn((
+            MassComponent::new(1.0e10),
+            Motion::new_position_velocity([1.0e11, 0.0], [0.0, 1.0e5]),
+        ));
+        let predictions = PredictionSystem::new();
+        predictions.predict(&mut world);
+        assert!(world.get::<&PredictionComponent>(star).is_err());
+        assert!(world.get::<&PredictionComponent>(comet).is_ok());
+
+        world.despawn(star).unwrap();
+        predictions.predict(&mut world);
+        assert!(world.get::<&PredictionComponent>(comet).is_err());
+    }
+}
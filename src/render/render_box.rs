@@ -26,6 +26,15 @@ impl RenderBoxComponent {
         self.bound
     }
 
+    /// rendering position of the center
+    pub fn position(&self) -> RenderingPosition {
+        self.position
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
     /// change rendering position to a new coordinates
     pub fn move_to(&mut self, position: RenderingPosition) {
         self.position = position;
@@ -1,13 +1,19 @@
-use hecs::World;
+use hecs::{Entity, World};
 use piston::input::RenderArgs;
 
 use crate::core::gl::SharedGraphics;
+use crate::core::text::SharedGlyphCache;
+use crate::physics::universe::Universe;
 use crate::render::background::BackgroundSystem;
+use crate::render::barycenter::BarycenterOverlaySystem;
 use crate::render::camera::{Camera, CameraSystem};
+use crate::render::hud::HudSystem;
+use crate::render::lagrange::LagrangeOverlaySystem;
 use crate::render::name::NameSystem;
+use crate::render::prediction::{Prediction, PredictionSystem};
+use crate::render::soi::SoiOverlaySystem;
 use crate::render::sprite::SpriteSystem;
-use crate::render::trace::{RenderTraceSystem, TraceSpawnSystem};
-use crate::text::SharedGlyphCache;
+use crate::render::trail::TrailSystem;
 use graphics::color::BLACK;
 use graphics::Graphics;
 
@@ -16,9 +22,13 @@ pub struct Renderer<'r> {
     camera_system: CameraSystem,
     circle_system: SpriteSystem,
     name_system: NameSystem,
-    trace_system: RenderTraceSystem,
-    trace_spawn_system: TraceSpawnSystem,
+    prediction_system: PredictionSystem,
+    soi_overlay: SoiOverlaySystem,
+    lagrange_overlay: LagrangeOverlaySystem,
+    barycenter_overlay: BarycenterOverlaySystem,
+    trail_system: TrailSystem,
     background: BackgroundSystem,
+    hud: HudSystem,
     glyphs: SharedGlyphCache<'r>,
 }
 
@@ -29,9 +39,13 @@ impl<'r> Renderer<'r> {
             camera_system: CameraSystem::new(camera),
             circle_system: SpriteSystem::default(),
             name_system: NameSystem::default(),
-            trace_system: RenderTraceSystem::default(),
-            trace_spawn_system: TraceSpawnSystem::default(),
+            prediction_system: PredictionSystem::default(),
+            soi_overlay: SoiOverlaySystem::default(),
+            lagrange_overlay: LagrangeOverlaySystem::default(),
+            barycenter_overlay: BarycenterOverlaySystem::default(),
+            trail_system: TrailSystem::default(),
             background: BackgroundSystem::default(),
+            hud: HudSystem::default(),
             glyphs,
         }
     }
@@ -40,20 +54,72 @@ impl<'r> Renderer<'r> {
         &mut self.camera_system.camera
     }
 
-    pub fn render(&mut self, args: RenderArgs, world: &mut World) {
+    pub fn camera_as_ref(&self) -> &Camera {
+        &self.camera_system.camera
+    }
+
+    /// returns true when spheres of influence are shown
+    pub fn toggle_soi_overlay(&mut self) -> bool {
+        self.soi_overlay.toggle()
+    }
+
+    pub fn toggle_barycenter(&mut self) {
+        self.barycenter_overlay.toggle();
+    }
+
+    /// show lagrange points of the primary and secondary, or hide them when the pair is shown
+    pub fn toggle_lagrange_points(&mut self, pair: (Entity, Entity)) {
+        if self.lagrange_overlay.pair() == Some(pair) {
+            self.lagrange_overlay.set_pair(None);
+        } else {
+            self.lagrange_overlay.set_pair(Some(pair));
+        }
+    }
+
+    /// show the message in the event log of the hud
+    pub fn log(&mut self, message: String) {
+        self.hud.log(message);
+    }
+
+    pub fn set_prediction(&mut self, prediction: Prediction) {
+        self.prediction_system.set_settings(prediction);
+    }
+
+    pub fn render(&mut self, args: RenderArgs, world: &mut World, universe: &Universe) {
         let gl = &mut (*self.gl).borrow_mut();
         let glyphs = &mut (*self.glyphs).borrow_mut();
 
-        let mut context = gl.draw_begin(args.viewport());
+        let screen = gl.draw_begin(args.viewport());
         gl.clear_color(BLACK);
-        self.background.update(world, context, gl, args);
+        self.background.update(world, screen, gl, args);
 
-        context = self.camera_system.update(context, world, args);
+        let context = self.camera_system.update(screen, world, args);
 
-        self.trace_spawn_system.update(world);
-        self.trace_system.update(world, context, gl);
+        self.trail_system.update(
+            world,
+            universe.elapsed(),
+            &self.camera_system.camera,
+            context,
+            gl,
+        );
+        self.prediction_system
+            .update(world, &self.camera_system.camera, context, gl);
+        self.soi_overlay
+            .update(world, &self.camera_system.camera, context, gl);
+        self.lagrange_overlay
+            .update(world, &self.camera_system.camera, glyphs, context, gl);
+        self.barycenter_overlay
+            .update(world, &self.camera_system.camera, context, gl);
         self.circle_system.update(world, context, gl);
         self.name_system.update(world, glyphs, context, gl);
+        self.hud.update(
+            world,
+            universe,
+            &self.camera_system.camera,
+            glyphs,
+            screen,
+            gl,
+        );
 
         gl.draw_end();
     }
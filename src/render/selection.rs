@@ -0,0 +1,212 @@
+use crate::core::tracking::TrackingComponent;
+use crate::physics::gravity::MassComponent;
+use crate::physics::motion::{distance_between, Motion, Position};
+use crate::physics::soi::SphereOfInfluenceComponent;
+use crate::render::camera::Camera;
+use crate::render::frame::ReferenceFrame;
+use crate::render::render_box::RenderBoxComponent;
+use hecs::{Entity, World};
+use std::collections::HashMap;
+
+/// bodies smaller than this radius on the screen are picked within it
+const PICK_RADIUS: f64 = 8.0;
+
+/// body under the cursor in window coordinates. the closest one wins when bodies overlap
+pub fn pick(world: &mut World, camera: &Camera, cursor: Position) -> Option<Entity> {
+    let point = vecmath::vec2_sub(cursor, camera.focus);
+    world
+        .query_mut::<(&RenderBoxComponent, &MassComponent)>()
+        .into_iter()
+        .map(|(id, (render_box, _))| {
+            let distance = distance_between(&render_box.position(), &point);
+            (id, distance, render_box.radius().max(PICK_RADIUS))
+        })
+        .filter(|(_, distance, radius)| distance <= radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _, _)| id)
+}
+
+/// move tracking to the entity. none leaves the camera free
+pub fn track(world: &mut World, entity: Option<Entity>) {
+    let tracked: Vec<Entity> = world
+        .query_mut::<&TrackingComponent>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    for id in tracked {
+        world.remove_one::<TrackingComponent>(id).unwrap();
+    }
+    if let Some(entity) = entity {
+        world.insert_one(entity, TrackingComponent::new()).unwrap();
+    }
+}
+
+/// track the next body in the order of entity ids, or the previous one backwards
+///
+/// ids follow the order bodies were loaded in, but ids of absorbed bodies are reused.
+pub fn cycle(world: &mut World, forward: bool) {
+    let mut bodies: Vec<(Entity, bool)> = world
+        .query_mut::<(
+            &MassComponent,
+            &RenderBoxComponent,
+            Option<&TrackingComponent>,
+        )>()
+        .into_iter()
+        .map(|(id, (_, _, tracking))| (id, tracking.is_some()))
+        .collect();
+    if bodies.is_empty() {
+        return;
+    }
+    bodies.sort_by_key(|(id, _)| id.id());
+    let count = bodies.len();
+    let next = match bodies.iter().position(|(_, tracked)| *tracked) {
+        Some(current) if forward => (current + 1) % count,
+        Some(current) => (current + count - 1) % count,
+        None if forward => 0,
+        None => count - 1,
+    };
+    track(world, Some(bodies[next].0));
+}
+
+/// free the camera and show every body
+pub fn fit_all(world: &mut World, camera: &mut Camera) {
+    let mut positions = world
+        .query_mut::<(&Motion, &MassComponent)>()
+        .into_iter()
+        .map(|(_, (motion, _))| camera.to_frame(motion.position));
+    let first = match positions.next() {
+        Some(first) => first,
+        None => return,
+    };
+    let (min, max) = positions.fold((first, first), |(min, max), p| {
+        (
+            [min[0].min(p[0]), min[1].min(p[1])],
+            [max[0].max(p[0]), max[1].max(p[1])],
+        )
+    });
+    track(world, None);
+    camera.fit(min, max);
+}
+
+/// show the tracked body with every body inside its sphere of influence
+///
+/// bodies without satellites are shown with their sphere of influence.
+pub fn fit_system(world: &mut World, camera: &mut Camera) {
+    let tracked = match tracked_body(world) {
+        Some(tracked) => tracked,
+        None => return fit_all(world, camera),
+    };
+    let center = world.get::<&Motion>(tracked).unwrap().position;
+    let mut primaries: HashMap<Entity, Option<Entity>> = HashMap::new();
+    let mut positions = vec![];
+    for (id, (motion, sphere)) in world.query_mut::<(&Motion, &SphereOfInfluenceComponent)>() {
+        primaries.insert(id, sphere.primary);
+        positions.push((id, motion.position));
+    }
+
+    let orbits = |id: Entity| {
+        let mut primary = primaries.get(&id).copied().flatten();
+        while let Some(current) = primary {
+            if current == tracked {
+                return true;
+            }
+            primary = primaries.get(&current).copied().flatten();
+        }
+        false
+    };
+    let radius = positions
+        .iter()
+        .filter(|(id, _)| orbits(*id))
+        .map(|(_, position)| distance_between(&center, position))
+        .fold(0.0, f64::max);
+    let radius = if radius > 0.0 {
+        radius
+    } else {
+        world
+            .get::<&SphereOfInfluenceComponent>(tracked)
+            .map_or(0.0, |sphere| sphere.radius)
+    };
+    if radius > 0.0 && radius.is_finite() {
+        camera.fit_radius(radius);
+    }
+}
+
+/// frame centered on the tracked body
+pub fn body_frame(world: &mut World) -> Option<ReferenceFrame> {
+    tracked_body(world).map(ReferenceFrame::Body)
+}
+
+/// frame co-rotating with the tracked body and the primary of its sphere of influence
+pub fn synodic_frame(world: &mut World) -> Option<ReferenceFrame> {
+    let (primary, secondary) = tracked_pair(world)?;
+    Some(ReferenceFrame::CoRotating { primary, secondary })
+}
+
+/// primary of the sphere of influence of the tracked body and the tracked body
+pub fn tracked_pair(world: &mut World) -> Option<(Entity, Entity)> {
+    let secondary = tracked_body(world)?;
+    let primary = world
+        .get::<&SphereOfInfluenceComponent>(secondary)
+        .ok()?
+        .primary?;
+    Some((primary, secondary))
+}
+
+fn tracked_body(world: &mut World) -> Option<Entity> {
+    world
+        .query_mut::<&TrackingComponent>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(world: &mut World) -> Vec<Entity> {
+        world
+            .query_mut::<&TrackingComponent>()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn cycle_through_bodies() {
+        let mut world = World::new();
+        let bodies: Vec<Entity> = (0..3)
+            .map(|_| {
+                world.spawn((
+                    MassComponent::new(1.0),
+                    RenderBoxComponent::centered_square(1.0),
+                ))
+            })
+            .collect();
+
+        cycle(&mut world, true);
+        assert_eq!(tracked(&mut world), vec![bodies[0]]);
+        cycle(&mut world, false);
+        assert_eq!(tracked(&mut world), vec![bodies[2]]);
+        cycle(&mut world, true);
+        assert_eq!(tracked(&mut world), vec![bodies[0]]);
+        track(&mut world, None);
+        assert!(tracked(&mut world).is_empty());
+    }
+
+    #[test]
+    fn pick_the_closest_body() {
+        let mut world = World::new();
+        let mut far = RenderBoxComponent::centered_square(16.0);
+        far.move_to([10.0, 0.0]);
+        let mut near = RenderBoxComponent::centered_square(2.0);
+        near.move_to([3.0, 0.0]);
+        world.spawn((MassComponent::new(1.0), far));
+        let near = world.spawn((MassComponent::new(1.0), near));
+        let mut camera = Camera::fixed(1.0);
+        camera.focus = [100.0, 100.0];
+
+        assert_eq!(pick(&mut world, &camera, [104.0, 100.0]), Some(near));
+        assert_eq!(pick(&mut world, &camera, [0.0, 0.0]), None);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::physics::motion::Motion;
+use crate::physics::soi::SphereOfInfluenceComponent;
+use crate::render::camera::Camera;
+use graphics::types::Color;
+use graphics::{Context, Ellipse};
+use hecs::World;
+use opengl_graphics::GlGraphics;
+
+const SOI_COLOR: Color = [0.4, 1.0, 0.6, 0.3];
+
+/// draws boundaries of spheres of influence. hidden by default
+pub struct SoiOverlaySystem {
+    visible: bool,
+}
+
+impl Default for SoiOverlaySystem {
+    fn default() -> Self {
+        SoiOverlaySystem::new()
+    }
+}
+
+impl SoiOverlaySystem {
+    pub fn new() -> Self {
+        SoiOverlaySystem { visible: false }
+    }
+
+    /// returns true when the overlay is shown
+    pub fn toggle(&mut self) -> bool {
+        self.visible = !self.visible;
+        self.visible
+    }
+
+    pub fn update(
+        &self,
+        world: &mut World,
+        camera: &Camera,
+        context: Context,
+        gl: &mut GlGraphics,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let border = Ellipse::new_border(SOI_COLOR, 0.5);
+        for (_id, (motion, sphere)) in &mut world.query::<(&Motion, &SphereOfInfluenceComponent)>()
+        {
+            if sphere.radius.is_infinite() {
+                continue;
+            }
+            let [x, y] = camera.project(motion.position);
+            let radius = camera.project_length(sphere.radius);
+            let bound = [x - radius, y - radius, radius * 2.0, radius * 2.0];
+            border.draw(bound, &context.draw_state, context.transform, gl);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::core::tracking::TrackingComponent;
+use crate::physics::motion::{Motion, Position};
+use crate::render::camera::Camera;
+use crate::render::frame::{FrameTransform, ReferenceFrame};
+use graphics::types::Color;
+use graphics::{Context, Ellipse, Line};
+use hecs::{Entity, World};
+use opengl_graphics::GlGraphics;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// upper bound of points kept for a trail
+const MAX_TRAIL_POINTS: usize = 512;
+const LINE_RADIUS: f64 = 0.5;
+const DOT_RADIUS: f64 = 1.0;
+
+/// how trails become transparent with age
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum Fade {
+    /// same opacity along the whole trail
+    None,
+    #[default]
+    Linear,
+    /// fades quicker, leaving a short bright tail
+    Quadratic,
+}
+
+impl Fade {
+    /// opacity of a point. freshness is one for the newest point and zero for the oldest
+    fn opacity(&self, freshness: f64) -> f32 {
+        let freshness = freshness.clamp(0.0, 1.0);
+        let opacity = match self {
+            Fade::None => 1.0,
+            Fade::Linear => freshness,
+            Fade::Quadratic => freshness * freshness,
+        };
+        opacity as f32
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum TrailStyle {
+    #[default]
+    Line,
+    Dots,
+}
+
+/// frame trails are drawn in on top of the reference frame of the camera
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+pub enum TrailFrame {
+    /// positions in the reference frame of the camera
+    #[default]
+    World,
+    /// positions relative to the tracked body, so moons draw closed orbits around a moving planet
+    ///
+    /// the trail starts over when the camera tracks another body.
+    Tracked,
+}
+
+/// trail settings of a body in the scenario
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Trail {
+    pub enabled: bool,
+    pub color: Color,
+    /// simulated seconds of history
+    pub length: f64,
+    pub fade: Fade,
+    pub style: TrailStyle,
+    pub frame: TrailFrame,
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Trail {
+            enabled: false,
+            color: [1.0, 1.0, 1.0, 0.5],
+            length: 86400.0,
+            fade: Fade::default(),
+            style: TrailStyle::default(),
+            frame: TrailFrame::default(),
+        }
+    }
+}
+
+/// position of the body in space recorded at the simulated time
+struct TrailPoint {
+    time: f64,
+    position: Position,
+    /// tracked body and its position at the same time
+    tracked: Option<(Entity, Position)>,
+    /// reference frame of the camera and its transform at the same time
+    frame: (ReferenceFrame, FrameTransform),
+}
+
+/// past positions of the body in a ring buffer
+pub struct TrailComponent {
+    settings: Trail,
+    points: VecDeque<TrailPoint>,
+}
+
+impl TrailComponent {
+    pub fn new(settings: Trail) -> Self {
+        TrailComponent {
+            settings,
+            points: VecDeque::with_capacity(MAX_TRAIL_POINTS),
+        }
+    }
+
+    /// remember the position unless the previous point is too recent. forget expired points
+    fn record(
+        &mut self,
+        time: f64,
+        position: Position,
+        tracked: Option<(Entity, Position)>,
+        frame: (ReferenceFrame, FrameTransform),
+    ) {
+        let spacing = self.settings.length / MAX_TRAIL_POINTS as f64;
+        match self.points.back() {
+            Some(last) if time - last.time < spacing => {}
+            _ => {
+                if self.points.len() == MAX_TRAIL_POINTS {
+                    self.points.pop_front();
+                }
+                self.points.push_back(TrailPoint {
+                    time,
+                    position,
+                    tracked,
+                    frame,
+                });
+            }
+        }
+        let oldest = time - self.settings.length;
+        while self.points.front().is_some_and(|point| point.time < oldest) {
+            self.points.pop_front();
+        }
+    }
+
+    /// position of the point in the current reference frame of the camera
+    ///
+    /// points recorded in another reference frame keep their place in it, so trails show
+    /// paths relative to the frame, e.g. orbits around lagrange points in a co-rotating frame.
+    /// none for points recorded in another kind of frame or while another body was tracked.
+    fn place(
+        &self,
+        point: &TrailPoint,
+        tracked: Option<(Entity, Position)>,
+        (frame, transform): (ReferenceFrame, FrameTransform),
+    ) -> Option<Position> {
+        let (recorded_frame, then) = point.frame;
+        if recorded_frame != frame {
+            return None;
+        }
+        let position = then.apply(point.position);
+        match self.settings.frame {
+            TrailFrame::World => Some(position),
+            TrailFrame::Tracked => match (point.tracked, tracked) {
+                (Some((entity, body)), Some((tracked, now))) if entity == tracked => {
+                    let offset = vecmath::vec2_sub(position, then.apply(body));
+                    Some(vecmath::vec2_add(transform.apply(now), offset))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// records and draws trails of bodies in the reference frame of the camera
+pub struct TrailSystem {}
+
+impl Default for TrailSystem {
+    fn default() -> Self {
+        TrailSystem::new()
+    }
+}
+
+impl TrailSystem {
+    pub fn new() -> Self {
+        TrailSystem {}
+    }
+
+    /// time is the simulated time of the frame
+    pub fn update(
+        &self,
+        world: &mut World,
+        time: f64,
+        camera: &Camera,
+        context: Context,
+        gl: &mut GlGraphics,
+    ) {
+        let tracked = world
+            .query_mut::<(&TrackingComponent, &Motion)>()
+            .into_iter()
+            .map(|(id, (_, motion))| (id, motion.position))
+            .next();
+        let frame = (camera.frame(), camera.transform());
+        for (_id, (trail, motion)) in world.query_mut::<(&mut TrailComponent, &Motion)>() {
+            trail.record(time, motion.position, tracked, frame);
+            let settings = &trail.settings;
+            let color = |recorded: f64| {
+                let mut color = settings.color;
+                color[3] *= settings
+                    .fade
+                    .opacity(1.0 - (time - recorded) / settings.length);
+                color
+            };
+            match settings.style {
+                TrailStyle::Line => {
+                    let mut previous = camera.project(motion.position);
+                    let points = trail.points.iter().rev().map_while(|point| {
+                        trail
+                            .place(point, tracked, frame)
+                            .map(|position| (point.time, position))
+                    });
+                    for (recorded, position) in points {
+                        let point = camera.project_local(position);
+                        Line::new(color(recorded), LINE_RADIUS).draw_from_to(
+                            previous,
+                            point,
+                            &context.draw_state,
+                            context.transform,
+                            gl,
+                        );
+                        previous = point;
+                    }
+                }
+                TrailStyle::Dots => {
+                    for point in trail.points.iter() {
+                        let position = match trail.place(point, tracked, frame) {
+                            Some(position) => position,
+                            None => continue,
+                        };
+                        let [x, y] = camera.project_local(position);
+                        let bound = [
+                            x - DOT_RADIUS,
+                            y - DOT_RADIUS,
+                            DOT_RADIUS * 2.0,
+                            DOT_RADIUS * 2.0,
+                        ];
+                        Ellipse::new(color(point.time)).draw(
+                            bound,
+                            &context.draw_state,
+                            context.transform,
+                            gl,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    const INERTIAL: (ReferenceFrame, FrameTransform) = (
+        ReferenceFrame::Inertial,
+        FrameTransform {
+            origin: [0.0, 0.0],
+            angle: 0.0,
+        },
+    );
+
+    #[test]
+    fn trail_keeps_limited_history() {
+        let settings = Trail {
+            enabled: true,
+            length: MAX_TRAIL_POINTS as f64,
+            ..Trail::default()
+        };
+        let mut trail = TrailComponent::new(settings);
+        for i in 0..4 * MAX_TRAIL_POINTS {
+            // several frames per recorded point
+            trail.record(i as f64 / 4.0, [i as f64, 0.0], None, INERTIAL);
+        }
+        let oldest = trail.points.front().unwrap().time;
+        let newest = trail.points.back().unwrap().time;
+        assert!(newest - oldest <= settings.length);
+        assert_eq!(trail.points.len(), MAX_TRAIL_POINTS);
+    }
+
+    #[test]
+    fn trail_relative_to_tracked_body() {
+        let mut world = World::new();
+        let planet = world.spawn(());
+        let other = world.spawn(());
+        let settings = Trail {
+            enabled: true,
+            frame: TrailFrame::Tracked,
+            ..Trail::default()
+        };
+        let mut trail = TrailComponent::new(settings);
+        trail.record(0.0, [11.0, 0.0], Some((planet, [10.0, 0.0])), INERTIAL);
+        let point = trail.points.back().unwrap();
+
+        assert_eq!(
+            trail.place(point, Some((planet, [20.0, 5.0])), INERTIAL),
+            Some([21.0, 5.0])
+        );
+        assert_eq!(
+            trail.place(point, Some((other, [20.0, 5.0])), INERTIAL),
+            None
+        );
+        assert_eq!(trail.place(point, None, INERTIAL), None);
+    }
+
+    #[test]
+    fn trail_keeps_place_in_rotating_frame() {
+        let mut world = World::new();
+        let primary = world.spawn(());
+        let secondary = world.spawn(());
+        let frame = ReferenceFrame::CoRotating { primary, secondary };
+        let mut trail = TrailComponent::new(Trail {
+            enabled: true,
+            ..Trail::default()
+        });
+        let then = FrameTransform {
+            origin: [0.0, 0.0],
+            angle: FRAC_PI_2,
+        };
+        trail.record(0.0, [0.0, 10.0], None, (frame, then));
+        let point = trail.points.back().unwrap();
+        let now = FrameTransform {
+            origin: [5.0, 5.0],
+            angle: PI,
+        };
+
+        let position = trail.place(point, None, (frame, now)).unwrap();
+        assert!(vecmath::vec2_len(vecmath::vec2_sub(position, [10.0, 0.0])) < 1.0e-9);
+        assert_eq!(trail.place(point, None, INERTIAL), None);
+    }
+
+    #[test]
+    fn fade_curves() {
+        assert_eq!(Fade::None.opacity(0.5), 1.0);
+        assert_eq!(Fade::Linear.opacity(0.5), 0.5);
+        assert_eq!(Fade::Quadratic.opacity(0.5), 0.25);
+        assert_eq!(Fade::Linear.opacity(-1.0), 0.0);
+    }
+}
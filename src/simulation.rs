@@ -1,5 +1,6 @@
 use piston::input::{
-    Button, ButtonEvent, ButtonState, Event, Key, MouseScrollEvent, RenderEvent, UpdateEvent,
+    Button, ButtonEvent, ButtonState, Event, Key, MouseButton, MouseCursorEvent, MouseScrollEvent,
+    RenderEvent, UpdateEvent,
 };
 
 use crate::core::events::EventHandler;
@@ -8,26 +9,48 @@ use crate::core::text::SharedGlyphCache;
 use crate::core::world::SharedWorld;
 use crate::physics::universe::Universe;
 use crate::render::camera::Camera;
+use crate::render::frame::ReferenceFrame;
 use crate::render::renderer::Renderer;
+use crate::render::selection;
+
+/// cursor movement in pixels that turns a click into a drag
+const DRAG_THRESHOLD: f64 = 4.0;
+/// camera movement in pixels per arrow key press
+const PAN_STEP: f64 = 64.0;
 
 pub struct SimulationStage<'a> {
     renderer: Renderer<'a>,
     universe: Universe,
     world: SharedWorld,
+    cursor: [f64; 2],
+    shift: bool,
+    /// distance the cursor moved since the left button was pressed
+    drag: Option<f64>,
 }
 
 impl<'a> SimulationStage<'a> {
-    pub fn new(gl: SharedGraphics, glyphs: SharedGlyphCache<'a>, world: SharedWorld) -> Self {
+    pub fn new(
+        gl: SharedGraphics,
+        glyphs: SharedGlyphCache<'a>,
+        world: SharedWorld,
+        universe: Universe,
+    ) -> Self {
         let camera = Camera::fixed(400.0 / 47.0 * 1.0e-6);
 
         let renderer = Renderer::camera(gl, camera, glyphs);
-        let universe = Universe::default();
         SimulationStage {
             renderer,
             universe,
             world,
+            cursor: [0.0, 0.0],
+            shift: false,
+            drag: None,
         }
     }
+
+    pub fn renderer_as_mut(&mut self) -> &mut Renderer<'a> {
+        &mut self.renderer
+    }
 }
 
 impl<'a> EventHandler for SimulationStage<'a> {
@@ -36,34 +59,124 @@ impl<'a> EventHandler for SimulationStage<'a> {
         let universe = &mut self.universe;
         let world = &mut (self.world).borrow_mut();
         if let Some(args) = e.render_args() {
-            renderer.render(args, world);
+            renderer.render(args, world, universe);
         }
 
         if let Some(args) = e.update_args() {
-            universe.update(args, world);
+            universe.update(args.dt, world);
+            for encounter in universe.take_encounters() {
+                println!("encounter: {}", encounter);
+                renderer.log(format!("encounter: {}", encounter));
+            }
+            for event in universe.take_soi_events() {
+                renderer.log(event.describe(world));
+            }
         }
         if let Some(args) = e.mouse_scroll_args() {
             if args[1] < 0.0 {
                 let camera = renderer.camera_as_mut();
-                camera.zoom_out();
+                camera.zoom_out(self.cursor);
             }
             if args[1] > 0.0 {
                 let camera = renderer.camera_as_mut();
-                camera.zoom_in();
+                camera.zoom_in(self.cursor);
+            }
+        }
+        if let Some(cursor) = e.mouse_cursor_args() {
+            let offset = vecmath::vec2_sub(cursor, self.cursor);
+            if let Some(dragged) = self.drag.as_mut() {
+                *dragged += vecmath::vec2_len(offset);
+                if *dragged > DRAG_THRESHOLD {
+                    selection::track(world, None);
+                    renderer.camera_as_mut().pan(offset);
+                }
             }
+            self.cursor = cursor;
         }
         if let Some(args) = e.button_args() {
+            if args.button == Button::Mouse(MouseButton::Left) {
+                match args.state {
+                    ButtonState::Press => self.drag = Some(0.0),
+                    ButtonState::Release => {
+                        let dragged = self.drag.take().unwrap_or(f64::INFINITY);
+                        if dragged <= DRAG_THRESHOLD {
+                            let camera = renderer.camera_as_ref();
+                            if let Some(body) = selection::pick(world, camera, self.cursor) {
+                                selection::track(world, Some(body));
+                            }
+                        }
+                    }
+                }
+            }
             if let Button::Keyboard(key) = args.button {
+                let pan = match key {
+                    Key::Left => Some([PAN_STEP, 0.0]),
+                    Key::Right => Some([-PAN_STEP, 0.0]),
+                    Key::Up => Some([0.0, PAN_STEP]),
+                    Key::Down => Some([0.0, -PAN_STEP]),
+                    _ => None,
+                };
+                if let (Some(offset), ButtonState::Press) = (pan, args.state) {
+                    selection::track(world, None);
+                    renderer.camera_as_mut().pan(offset);
+                }
                 match key {
+                    Key::LShift | Key::RShift => {
+                        self.shift = args.state == ButtonState::Press;
+                    }
+                    Key::Tab if args.state == ButtonState::Press => {
+                        selection::cycle(world, !self.shift);
+                    }
+                    Key::F if args.state == ButtonState::Press => {
+                        selection::track(world, None);
+                    }
+                    Key::A if args.state == ButtonState::Press => {
+                        selection::fit_all(world, renderer.camera_as_mut());
+                    }
+                    Key::S if args.state == ButtonState::Press => {
+                        universe.refresh_spheres_of_influence(world);
+                        selection::fit_system(world, renderer.camera_as_mut());
+                    }
                     Key::Comma => {
                         universe.slow_down();
                     }
                     Key::Period => {
                         universe.speed_up();
                     }
-                    Key::P => {
-                        if args.state == ButtonState::Press {
-                            universe.toggle_pause();
+                    Key::P if args.state == ButtonState::Press => {
+                        universe.toggle_pause();
+                    }
+                    Key::O if args.state == ButtonState::Press => {
+                        let shown = renderer.toggle_soi_overlay();
+                        universe.show_spheres_of_influence(shown);
+                        universe.refresh_spheres_of_influence(world);
+                    }
+                    Key::B if args.state == ButtonState::Press => {
+                        renderer.toggle_barycenter();
+                    }
+                    Key::L if args.state == ButtonState::Press => {
+                        universe.refresh_spheres_of_influence(world);
+                        if let Some(pair) = selection::tracked_pair(world) {
+                            renderer.toggle_lagrange_points(pair);
+                        }
+                    }
+                    Key::D1 if args.state == ButtonState::Press => {
+                        renderer.camera_as_mut().set_frame(ReferenceFrame::Inertial);
+                    }
+                    Key::D2 if args.state == ButtonState::Press => {
+                        renderer
+                            .camera_as_mut()
+                            .set_frame(ReferenceFrame::Barycentric);
+                    }
+                    Key::D3 if args.state == ButtonState::Press => {
+                        if let Some(frame) = selection::body_frame(world) {
+                            renderer.camera_as_mut().set_frame(frame);
+                        }
+                    }
+                    Key::D4 if args.state == ButtonState::Press => {
+                        universe.refresh_spheres_of_influence(world);
+                        if let Some(frame) = selection::synodic_frame(world) {
+                            renderer.camera_as_mut().set_frame(frame);
                         }
                     }
                     _ => {}